@@ -0,0 +1,424 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::cart::{current_status, find_public_record, get_current_items_impl, CartProductWithHash};
+use crate::delivery_proof::get_delivery_address_for_caller;
+use crate::fulfillment::get_order_fulfillment_impl;
+use crate::order::require_order_assignment;
+use crate::properties::get_cart_properties;
+use crate::recurring::call_session_for;
+
+// Bound on 2-opt improvement passes - batches are small, this only guards against float ping-pong
+pub const MAX_TWO_OPT_PASSES: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+// What the batch planner needs from one order - served by that order's cart session cell
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchOrderSummary {
+    pub assignment_hash: ActionHash,
+    pub shopper: AgentPubKey,
+    pub status: Option<String>,
+    pub store_id: Option<String>,
+    pub time_slot: Option<DeliveryTimeSlot>,
+    pub is_pickup: bool,
+    // None for pickup orders, or until the customer shares the sealed address with the shopper
+    pub delivery_point: Option<GeoPoint>,
+    pub lines: Vec<CartProductWithHash>,
+}
+
+// Why a set of orders cannot be shopped together
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum BatchConflict {
+    WrongBatchSize { orders: usize, max: usize },
+    NotYourOrder { dna_hash: DnaHash },
+    NotClaimed { dna_hash: DnaHash, status: Option<String> },
+    DifferentStores { store_ids: Vec<Option<String>> },
+    MissingTimeSlot { dna_hash: DnaHash },
+    SlotsDoNotOverlap { time_slots: Vec<DeliveryTimeSlot> },
+    AddressNotShared { dna_hash: DnaHash },
+}
+
+// One order's share of a merged pick line
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchAllocation {
+    pub assignment_hash: ActionHash,
+    pub cart_product_hash: ActionHash,
+    pub quantity: f64,
+}
+
+// Identical product ids across the batch, picked once and split at packing
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchPickLine {
+    pub product_id: String,
+    pub product_name: String,
+    pub upc: Option<String>,
+    pub sold_by: Option<String>,
+    pub total_quantity: f64,
+    pub allocations: Vec<BatchAllocation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchStop {
+    pub assignment_hash: ActionHash,
+    pub point: GeoPoint,
+    pub leg_meters: f64, // From the previous stop (or the start)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchPlan {
+    pub batch_hash: ActionHash,
+    pub orders: Vec<BatchOrder>,
+    pub pick_list: Vec<BatchPickLine>,
+    pub stops: Vec<BatchStop>,
+    pub total_distance_meters: f64,
+}
+
+// Served by each order cell to the shopper's batch planner
+pub(crate) fn get_batch_order_summary_impl() -> ExternResult<BatchOrderSummary> {
+    let (assignment_hash, assignment) = require_order_assignment()?;
+
+    let pickup_store = match get_order_fulfillment_impl()? {
+        Some((_, OrderFulfillment { mode: FulfillmentMode::Pickup, store: Some(store) })) => Some(store),
+        _ => None,
+    };
+    let delivery_point = match &pickup_store {
+        Some(_) => None,
+        None => get_delivery_address_for_caller()?
+            .and_then(|(_, address)| address)
+            .map(|address| GeoPoint { lat: address.lat, lng: address.lng }),
+    };
+
+    Ok(BatchOrderSummary {
+        assignment_hash,
+        shopper: assignment.shopper,
+        status: current_status()?,
+        is_pickup: pickup_store.is_some(),
+        store_id: match pickup_store {
+            Some(store) => Some(store.store_id),
+            None => get_cart_properties()?.store_id,
+        },
+        time_slot: find_public_record::<DeliveryTimeSlot>()?.map(|(_, time_slot)| time_slot),
+        delivery_point,
+        lines: get_current_items_impl()?,
+    })
+}
+
+// "2pm", "9:30am", "14:00" -> minutes after midnight
+fn parse_clock_time(text: &str) -> Option<u32> {
+    let text = text.trim().to_lowercase();
+    let (clock, meridiem) = if let Some(clock) = text.strip_suffix("am") {
+        (clock.trim(), Some(false))
+    } else if let Some(clock) = text.strip_suffix("pm") {
+        (clock.trim(), Some(true))
+    } else {
+        (text.as_str(), None)
+    };
+    let (hours, minutes) = match clock.split_once(':') {
+        Some((hours, minutes)) => (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    let hours = match meridiem {
+        Some(is_pm) if (1..=12).contains(&hours) => hours % 12 + if is_pm { 12 } else { 0 },
+        Some(_) => return None,
+        None if hours < 24 => hours,
+        None => return None,
+    };
+    Some(hours * 60 + minutes)
+}
+
+// "2pm-4pm" -> (840, 960)
+fn parse_time_window(time_slot: &str) -> Option<(u32, u32)> {
+    let (start, end) = time_slot.split_once('-')?;
+    let (start, end) = (parse_clock_time(start)?, parse_clock_time(end)?);
+    if end > start {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+// Every slot is on the same day and all windows share some time
+fn slots_overlap(time_slots: &[DeliveryTimeSlot]) -> bool {
+    let first = match time_slots.first() {
+        Some(first) => first,
+        None => return true,
+    };
+    if time_slots.iter().any(|time_slot| time_slot.date != first.date) {
+        return false;
+    }
+    let windows: Option<Vec<(u32, u32)>> = time_slots
+        .iter()
+        .map(|time_slot| parse_time_window(&time_slot.time_slot))
+        .collect();
+    match windows {
+        Some(windows) => {
+            let latest_start = windows.iter().map(|(start, _)| *start).max().unwrap_or(0);
+            let earliest_end = windows.iter().map(|(_, end)| *end).min().unwrap_or(0);
+            latest_start < earliest_end
+        }
+        // Windows we cannot read only batch with the exact same window
+        None => time_slots.iter().all(|time_slot| time_slot.time_slot == first.time_slot),
+    }
+}
+
+fn find_conflicts(summaries: &[(DnaHash, BatchOrderSummary)], me: &AgentPubKey) -> Vec<BatchConflict> {
+    let mut conflicts = Vec::new();
+    if summaries.len() < 2 || summaries.len() > MAX_BATCH_ORDERS {
+        conflicts.push(BatchConflict::WrongBatchSize { orders: summaries.len(), max: MAX_BATCH_ORDERS });
+    }
+
+    let mut time_slots = Vec::new();
+    for (dna_hash, summary) in summaries {
+        if summary.shopper != *me {
+            conflicts.push(BatchConflict::NotYourOrder { dna_hash: dna_hash.clone() });
+        }
+        if summary.status.as_deref() != Some("Claimed") {
+            conflicts.push(BatchConflict::NotClaimed { dna_hash: dna_hash.clone(), status: summary.status.clone() });
+        }
+        match &summary.time_slot {
+            Some(time_slot) => time_slots.push(time_slot.clone()),
+            None => conflicts.push(BatchConflict::MissingTimeSlot { dna_hash: dna_hash.clone() }),
+        }
+        if !summary.is_pickup && summary.delivery_point.is_none() {
+            conflicts.push(BatchConflict::AddressNotShared { dna_hash: dna_hash.clone() });
+        }
+    }
+
+    let store_ids: Vec<Option<String>> = summaries.iter().map(|(_, summary)| summary.store_id.clone()).collect();
+    if store_ids.iter().any(|store_id| *store_id != store_ids[0]) {
+        conflicts.push(BatchConflict::DifferentStores { store_ids });
+    }
+    if !slots_overlap(&time_slots) {
+        conflicts.push(BatchConflict::SlotsDoNotOverlap { time_slots });
+    }
+    conflicts
+}
+
+// Merge identical product ids across orders, keeping each order's share
+fn merge_pick_lists(summaries: &[(DnaHash, BatchOrderSummary)]) -> Vec<BatchPickLine> {
+    let mut merged: BTreeMap<String, BatchPickLine> = BTreeMap::new();
+    for (_, summary) in summaries {
+        for line in &summary.lines {
+            let pick_line = merged.entry(line.product.product_id.clone()).or_insert_with(|| BatchPickLine {
+                product_id: line.product.product_id.clone(),
+                product_name: line.product.product_name.clone(),
+                upc: line.product.upc.clone(),
+                sold_by: line.product.sold_by.clone(),
+                total_quantity: 0.0,
+                allocations: Vec::new(),
+            });
+            pick_line.total_quantity += line.quantity;
+            pick_line.allocations.push(BatchAllocation {
+                assignment_hash: summary.assignment_hash.clone(),
+                cart_product_hash: line.action_hash.clone(),
+                quantity: line.quantity,
+            });
+        }
+    }
+    let mut pick_list: Vec<BatchPickLine> = merged.into_values().collect();
+    pick_list.sort_by(|a, b| a.product_name.cmp(&b.product_name));
+    pick_list
+}
+
+fn leg(from: &GeoPoint, to: &GeoPoint) -> f64 {
+    distance_meters(from.lat, from.lng, to.lat, to.lng)
+}
+
+// Greedy route from the start - always drive to the closest remaining stop
+fn nearest_neighbour_route(start: &GeoPoint, points: &[GeoPoint]) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut route = Vec::with_capacity(points.len());
+    let mut current = *start;
+    while !remaining.is_empty() {
+        let mut best = 0;
+        for (candidate, &index) in remaining.iter().enumerate().skip(1) {
+            if leg(&current, &points[index]) < leg(&current, &points[remaining[best]]) {
+                best = candidate;
+            }
+        }
+        let next = remaining.remove(best);
+        current = points[next];
+        route.push(next);
+    }
+    route
+}
+
+// Reverse stretches of the route while that shortens it - the route is open (no return to the store)
+fn two_opt(start: &GeoPoint, points: &[GeoPoint], mut route: Vec<usize>) -> Vec<usize> {
+    let stops = route.len();
+    for _ in 0..MAX_TWO_OPT_PASSES {
+        let mut improved = false;
+        for i in 0..stops.saturating_sub(1) {
+            for j in (i + 1)..stops {
+                let before = if i == 0 { *start } else { points[route[i - 1]] };
+                let (first, last) = (points[route[i]], points[route[j]]);
+                let after = route.get(j + 1).map(|&index| points[index]);
+
+                let current = leg(&before, &first) + after.map_or(0.0, |after| leg(&last, &after));
+                let reversed = leg(&before, &last) + after.map_or(0.0, |after| leg(&first, &after));
+                if reversed + 1e-6 < current {
+                    route[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    route
+}
+
+// Check the orders can be shopped together, merge their pick lists and order the delivery stops.
+// Call on the shopper's main cart cell; each order is the shopper's cell for that order's DNA hash.
+pub(crate) fn create_batch_impl(order_dna_hashes: Vec<DnaHash>, start: GeoPoint) -> ExternResult<BatchPlan> {
+    let me = agent_info()?.agent_initial_pubkey;
+
+    let mut summaries = Vec::new();
+    for dna_hash in order_dna_hashes {
+        let cell_id = CellId::new(dna_hash.clone(), me.clone());
+        let summary: BatchOrderSummary = call_session_for(&cell_id, "get_batch_order_summary", ())?;
+        summaries.push((dna_hash, summary));
+    }
+
+    let conflicts = find_conflicts(&summaries, &me);
+    if !conflicts.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "These orders cannot be batched: {:?}", conflicts
+        ))));
+    }
+
+    let pick_list = merge_pick_lists(&summaries);
+
+    let deliveries: Vec<(ActionHash, GeoPoint)> = summaries
+        .iter()
+        .filter_map(|(_, summary)| summary.delivery_point.map(|point| (summary.assignment_hash.clone(), point)))
+        .collect();
+    let points: Vec<GeoPoint> = deliveries.iter().map(|(_, point)| *point).collect();
+    let route = two_opt(&start, &points, nearest_neighbour_route(&start, &points));
+
+    let mut stops = Vec::with_capacity(route.len());
+    let mut previous = start;
+    for index in route {
+        let (assignment_hash, point) = deliveries[index].clone();
+        stops.push(BatchStop { assignment_hash, point, leg_meters: leg(&previous, &point) });
+        previous = point;
+    }
+    let total_distance_meters = stops.iter().map(|stop| stop.leg_meters).sum();
+
+    let orders: Vec<BatchOrder> = summaries
+        .iter()
+        .map(|(dna_hash, summary)| BatchOrder {
+            dna_hash: dna_hash.clone(),
+            assignment_hash: summary.assignment_hash.clone(),
+        })
+        .collect();
+    let batch_hash = create_entry(EntryTypes::ShopperBatch(ShopperBatch {
+        orders: orders.clone(),
+        stop_order: stops.iter().map(|stop| stop.assignment_hash.clone()).collect(),
+        created_at: sys_time()?.as_micros() as u64,
+    }))?;
+
+    warn!(
+        "🧺 BATCH: {} orders, {} merged pick lines, {} stops over {:.0}m",
+        orders.len(), pick_list.len(), stops.len(), total_distance_meters
+    );
+
+    Ok(BatchPlan {
+        batch_hash,
+        orders,
+        pick_list,
+        stops,
+        total_distance_meters,
+    })
+}
+
+// The shopper's batches, newest first
+pub(crate) fn get_shopper_batches_impl() -> ExternResult<Vec<(ActionHash, ShopperBatch)>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::ShopperBatch.try_into()?)
+            .include_entries(true),
+    )?;
+    let mut batches: Vec<(ActionHash, ShopperBatch)> = records
+        .into_iter()
+        .filter_map(|record| {
+            let hash = record.action_address().clone();
+            ShopperBatch::try_from(record).ok().map(|batch| (hash, batch))
+        })
+        .collect();
+    batches.reverse();
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time_slot(date: u64, window: &str) -> DeliveryTimeSlot {
+        DeliveryTimeSlot { date, time_slot: window.to_string() }
+    }
+
+    #[test]
+    fn parse_time_window_reads_twelve_and_twenty_four_hour_clocks() {
+        assert_eq!(parse_time_window("2pm-4pm"), Some((840, 960)));
+        assert_eq!(parse_time_window("9:30am - 11AM"), Some((570, 660)));
+        assert_eq!(parse_time_window("14:00-16:30"), Some((840, 990)));
+        assert_eq!(parse_time_window("12am-12pm"), Some((0, 720)));
+    }
+
+    #[test]
+    fn parse_time_window_rejects_bad_windows() {
+        assert_eq!(parse_time_window("4pm-2pm"), None);
+        assert_eq!(parse_time_window("2pm-2pm"), None);
+        assert_eq!(parse_time_window("13pm-2pm"), None);
+        assert_eq!(parse_time_window("10:75-11:00"), None);
+        assert_eq!(parse_time_window("2pm"), None);
+        assert_eq!(parse_time_window("morning"), None);
+    }
+
+    #[test]
+    fn slots_overlap_needs_shared_time_on_the_same_day() {
+        assert!(slots_overlap(&[]));
+        assert!(slots_overlap(&[time_slot(1, "2pm-4pm"), time_slot(1, "3pm-5pm"), time_slot(1, "1pm-3:30pm")]));
+        assert!(!slots_overlap(&[time_slot(1, "2pm-3pm"), time_slot(1, "3pm-4pm")]));
+        assert!(!slots_overlap(&[time_slot(1, "2pm-4pm"), time_slot(2, "2pm-4pm")]));
+    }
+
+    #[test]
+    fn slots_overlap_only_batches_unreadable_windows_that_match() {
+        assert!(slots_overlap(&[time_slot(1, "morning"), time_slot(1, "morning")]));
+        assert!(!slots_overlap(&[time_slot(1, "morning"), time_slot(1, "2pm-4pm")]));
+    }
+
+    #[test]
+    fn two_opt_untangles_an_open_route() {
+        let start = GeoPoint { lat: 0.0, lng: 0.0 };
+        let points = [
+            GeoPoint { lat: 0.0, lng: 0.03 },
+            GeoPoint { lat: 0.0, lng: 0.01 },
+            GeoPoint { lat: 0.0, lng: 0.02 },
+        ];
+        assert_eq!(two_opt(&start, &points, vec![0, 1, 2]), vec![1, 2, 0]);
+        assert_eq!(two_opt(&start, &points, vec![1, 2, 0]), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn two_opt_handles_tiny_routes() {
+        let start = GeoPoint { lat: 0.0, lng: 0.0 };
+        let points = [GeoPoint { lat: 0.0, lng: 0.01 }];
+        assert_eq!(two_opt(&start, &points, vec![0]), vec![0]);
+        assert_eq!(two_opt(&start, &[], Vec::new()), Vec::<usize>::new());
+    }
+}
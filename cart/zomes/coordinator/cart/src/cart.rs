@@ -2,6 +2,8 @@ use cart_integrity::*;
 use hdk::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::encryption::{open_delivery_data, seal_address, seal_delivery_data};
+
 // Helper struct that includes both the cart product and its hash for removal
 // Now includes quantity and timestamp from link tags
 #[derive(Serialize, Deserialize, Debug)]
@@ -21,14 +23,53 @@ pub struct CartSessionData {
     pub address: Option<Record>,
     pub delivery_time_slot: Option<Record>,
     pub delivery_instructions: Option<Record>,
+    // Plaintext views of the sealed address/instructions - None unless the caller is a recipient
+    pub decrypted_address: Option<Address>,
+    pub decrypted_instructions: Option<DeliveryInstructions>,
+    // Sealed gift recipient and message - decrypted_gift is None unless the caller is a recipient
+    #[serde(default)]
+    pub gift_details: Option<Record>,
+    #[serde(default)]
+    pub decrypted_gift: Option<GiftDetails>,
+    // Delivery or pickup - None for sessions that never chose (delivered)
+    #[serde(default)]
+    pub fulfillment: Option<OrderFulfillment>,
+    // Read from this node's store only - may be stale, but works offline
+    #[serde(default)]
+    pub local_only: bool,
+    // Cart changes queued offline and not yet replayed
+    #[serde(default)]
+    pub pending_ops: Vec<crate::offline::QueuedCartOpWithHash>,
 }
 
 // Helper function to get PUBLIC path that all agents can see
-fn get_public_cart_path() -> ExternResult<Path> {
+pub(crate) fn get_public_cart_path() -> ExternResult<Path> {
     let path_str = "active_carts";
     Path::try_from(path_str).map_err(|_| wasm_error!(WasmErrorInner::Guest("Failed to create public path".to_string())))
 }
 
+// Helper to find the first record of a given entry type linked from the PUBLIC path
+pub(crate) fn find_public_record<T: TryFrom<Record>>() -> ExternResult<Option<(Record, T)>> {
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+    
+    let links = get_links(
+        GetLinksInputBuilder::try_new(public_hash, LinkTypes::PublicPathToCartData)?.build()
+    )?;
+    
+    for link in links {
+        if let Some(target_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(target_hash, GetOptions::default())? {
+                if let Ok(entry) = T::try_from(record.clone()) {
+                    return Ok(Some((record, entry)));
+                }
+            }
+        }
+    }
+    
+    Ok(None)
+}
+
 // Add individual cart item - OPTIMIZED: create entry only if needed, update quantity via link tags
 pub(crate) fn add_item_impl(item: CartProduct, quantity: f64) -> ExternResult<ActionHash> {
     let public_path = get_public_cart_path()?;
@@ -37,12 +78,20 @@ pub(crate) fn add_item_impl(item: CartProduct, quantity: f64) -> ExternResult<Ac
     
     // Check if this product already exists in the cart
     let existing_entry = find_existing_cart_product(&item.product_id)?;
+
+    let new_quantity = quantity + existing_entry.as_ref().map_or(0.0, |(_, current, _)| *current);
+    if let Some(max_quantity) = item.max_quantity {
+        if new_quantity > max_quantity {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "{} is limited to {} per order", item.product_name, max_quantity
+            ))));
+        }
+    }
     
-    let cart_product_hash = if let Some((existing_hash, current_quantity, _)) = existing_entry {
+    let cart_product_hash = if let Some((existing_hash, _, _)) = existing_entry {
         // Product exists - delete old link and create new link with updated quantity
         delete_quantity_link(&public_hash, &existing_hash)?;
         
-        let new_quantity = current_quantity + quantity;
         let quantity_tag = CartQuantityTag {
             quantity: new_quantity,
             timestamp,
@@ -58,7 +107,7 @@ pub(crate) fn add_item_impl(item: CartProduct, quantity: f64) -> ExternResult<Ac
         existing_hash
     } else {
         // Product doesn't exist - create new entry and link with quantity tag
-        let cart_product_hash = create_entry(EntryTypes::CartProduct(item))?;
+        let cart_product_hash = create_entry(EntryTypes::CartProduct(item.migrated()))?;
         
         let quantity_tag = CartQuantityTag {
             quantity,
@@ -220,64 +269,99 @@ pub(crate) fn get_session_status_impl() -> ExternResult<Option<Record>> {
 }
 
 // Update session status to "Checkout" using PUBLIC path - ALL status changes are public
-pub(crate) fn publish_order_impl() -> ExternResult<ActionHash> {
+pub(crate) fn publish_order_impl(age_confirmed: bool) -> ExternResult<ActionHash> {
     warn!("🚀 PUBLISH ORDER: Starting publish_order_impl");
     
-    let current_time = sys_time()?.as_micros() as u64;
-    let public_path = get_public_cart_path()?;
-    let public_hash = public_path.path_entry_hash()?;
-    
-    let new_status = SessionStatus {
-        status: "Checkout".to_string(),
-        last_updated: current_time,
-    };
+    // Address, slot, basket and age checks come first so every problem is reported together
+    crate::checkout::require_valid_checkout(age_confirmed)?;
     
-    warn!("📝 PUBLISH ORDER: Creating SessionStatus with status: Checkout, timestamp: {}", current_time);
+    // Payment must be authorized for (at least) the current cart estimate
+    let payment_intent_hash = crate::payment::require_authorized_payment()?;
     
-    if let Some(status_record) = get_session_status_impl()? {
-        let new_hash = update_entry(status_record.action_address().clone(), new_status)?;
-        
-        // Find and delete only the link pointing to the old SessionStatus
-        let links = get_links(
-            GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
-        )?;
-        for link in links {
-            if let Some(target_hash) = link.target.clone().into_action_hash() {
-                if target_hash == *status_record.action_address() {
-                    delete_link(link.create_link_hash)?;
-                    break;
-                }
-            }
-        }
-        create_link(public_hash, new_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
-        
-        warn!("✅ PUBLISH ORDER: SessionStatus updated with hash: {:?}", new_hash);
-        Ok(new_hash)
-    } else {
-        let status_hash = create_entry(EntryTypes::SessionStatus(new_status))?;
-        
-        create_link(
-            public_hash,
-            status_hash.clone(),
-            LinkTypes::PublicPathToCartData,
-            ()
-        )?;
-        
-        warn!("✅ PUBLISH ORDER: SessionStatus created with hash: {:?}", status_hash);
-        Ok(status_hash)
-    }
+    let gift = crate::gift::get_gift_details_impl()?;
+    let delivery_time_slot_hash =
+        find_public_record::<DeliveryTimeSlot>()?.map(|(record, _)| record.action_address().clone());
+    let handover_hash = crate::delivery_proof::get_handover_hash()?;
+    let status_hash = write_session_status_entry(SessionStatus {
+        status: "Checkout".to_string(),
+        last_updated: sys_time()?.as_micros() as u64,
+        delivery_proof_hash: None,
+        contains_restricted_items: cart_contains_restricted_items()?,
+        cancellation: None,
+        age_confirmed,
+        is_gift: gift.is_some(),
+        hide_prices: gift.is_some_and(|gift| gift.hide_prices),
+        assignment_hash: None,
+        payment_intent_hash: Some(payment_intent_hash),
+        delivery_time_slot_hash,
+        handover_hash,
+    })?;
+    
+    warn!("✅ PUBLISH ORDER: SessionStatus written with hash: {:?}", status_hash);
+    Ok(status_hash)
 }
 
 // Update session status back to "Shopping" using PUBLIC path - ALL status changes are public
+// Only a published order nobody has claimed yet can be recalled; after that it has to be cancelled
 pub(crate) fn recall_order_impl() -> ExternResult<ActionHash> {
-    let current_time = sys_time()?.as_micros() as u64;
+    if crate::order::get_order_assignment_impl()?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "This order has been claimed by a shopper - cancel it instead of recalling it".to_string()
+        )));
+    }
+    if let Some(status) = current_status()? {
+        if !is_allowed_status_transition(&status, "Shopping") {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "An order in {} cannot be recalled", status
+            ))));
+        }
+    }
+    write_session_status("Shopping")
+}
+
+// Shared status writer for every status that does not carry extra data
+pub(crate) fn write_session_status(status: &str) -> ExternResult<ActionHash> {
+    write_session_status_entry(next_session_status(status)?)
+}
+
+// The next status built from the current one - what was fixed at publish (age confirmation, gift flags,
+// payment, slot, handover point) and the assignment are carried forward; per-status data starts empty
+pub(crate) fn next_session_status(status: &str) -> ExternResult<SessionStatus> {
+    let current = current_session_status()?;
+    Ok(SessionStatus {
+        status: status.to_string(),
+        last_updated: sys_time()?.as_micros() as u64,
+        delivery_proof_hash: None,
+        contains_restricted_items: published_contains_restricted_items()?,
+        cancellation: None,
+        age_confirmed: current.as_ref().is_some_and(|current| current.age_confirmed),
+        is_gift: current.as_ref().is_some_and(|current| current.is_gift),
+        hide_prices: current.as_ref().is_some_and(|current| current.hide_prices),
+        assignment_hash: current.as_ref().and_then(|current| current.assignment_hash.clone()),
+        payment_intent_hash: current.as_ref().and_then(|current| current.payment_intent_hash.clone()),
+        delivery_time_slot_hash: current.as_ref().and_then(|current| current.delivery_time_slot_hash.clone()),
+        handover_hash: current.and_then(|current| current.handover_hash),
+    })
+}
+
+// Whether any current cart line is age-restricted
+pub(crate) fn cart_contains_restricted_items() -> ExternResult<bool> {
+    Ok(get_current_items_impl()?.iter().any(|item| item.product.age_restricted))
+}
+
+// Restricted flag for a status after publish - kept once set, even if restricted lines were removed since
+pub(crate) fn published_contains_restricted_items() -> ExternResult<bool> {
+    let published = current_session_status()?
+        .is_some_and(|current| current.status != "Shopping" && current.contains_restricted_items);
+    Ok(published || cart_contains_restricted_items()?)
+}
+
+// Update the existing SessionStatus (relinking the public path) or create the first one
+pub(crate) fn write_session_status_entry(new_status: SessionStatus) -> ExternResult<ActionHash> {
     let public_path = get_public_cart_path()?;
     let public_hash = public_path.path_entry_hash()?;
     
-    let new_status = SessionStatus {
-        status: "Shopping".to_string(),
-        last_updated: current_time,
-    };
+    warn!("📝 SESSION STATUS: Writing status: {}, timestamp: {}", new_status.status, new_status.last_updated);
     
     if let Some(status_record) = get_session_status_impl()? {
         let new_hash = update_entry(status_record.action_address().clone(), new_status)?;
@@ -296,7 +380,6 @@ pub(crate) fn recall_order_impl() -> ExternResult<ActionHash> {
         }
         create_link(public_hash, new_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
         
-        warn!("✅ PUBLISH ORDER: SessionStatus updated with hash: {:?}", new_hash);
         Ok(new_hash)
     } else {
         let status_hash = create_entry(EntryTypes::SessionStatus(new_status))?;
@@ -312,20 +395,37 @@ pub(crate) fn recall_order_impl() -> ExternResult<ActionHash> {
     }
 }
 
-// Set delivery address for first time - create_entry + create_link to PUBLIC path
+// Read the current SessionStatus entry, if one exists
+pub(crate) fn current_session_status() -> ExternResult<Option<SessionStatus>> {
+    Ok(get_session_status_impl()?.and_then(|record| SessionStatus::try_from(record).ok()))
+}
+
+// Read the current status string, if a SessionStatus exists
+pub(crate) fn current_status() -> ExternResult<Option<String>> {
+    Ok(current_session_status()?.map(|status| status.status))
+}
+
+// Set delivery address for first time - sealed to the customer (and assigned shopper) + create_link to PUBLIC path
 pub(crate) fn set_delivery_address_impl(address: Address) -> ExternResult<ActionHash> {
+    if let Some(status) = current_status()? {
+        if status != "Shopping" {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "The delivery address can only be changed while shopping (status is {})", status
+            ))));
+        }
+    }
     let public_path = get_public_cart_path()?;
     let public_hash = public_path.path_entry_hash()?;
     
-    warn!("🛒 CART DNA: Creating PUBLIC address entry for cart session: {} {}, {}", 
-           address.street, address.city, address.state);
+    warn!("🛒 CART DNA: Creating encrypted address entry for cart session");
     
-    // Create the Address entry
-    let address_hash = create_entry(EntryTypes::Address(address))?;
+    // Seal the Address - only envelope recipients can read street, unit and coordinates
+    let sealed = seal_address(&address)?;
+    let address_hash = create_entry(EntryTypes::EncryptedDeliveryData(sealed))?;
     
-    warn!("✅ CART DNA: Public address created with hash: {:?}", address_hash);
+    warn!("✅ CART DNA: Encrypted address created with hash: {:?}", address_hash);
     
-    // Link it to the PUBLIC path so all agents can see it
+    // Link it to the PUBLIC path - the envelope is public, the content is not
     create_link(
         public_hash,
         address_hash.clone(),
@@ -336,15 +436,21 @@ pub(crate) fn set_delivery_address_impl(address: Address) -> ExternResult<Action
     Ok(address_hash)
 }
 
-// Update delivery address - delete old link, create new entry, create new link
+// Update delivery address - delete old link, create new sealed entry, create new link
 pub(crate) fn update_delivery_address_impl(previous_address_hash: ActionHash, new_address: Address) -> ExternResult<ActionHash> {
+    if let Some(status) = current_status()? {
+        if status != "Shopping" {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "The delivery address can only be changed while shopping (status is {})", status
+            ))));
+        }
+    }
     let public_path = get_public_cart_path()?;
     let public_hash = public_path.path_entry_hash()?;
     
-    warn!("🔄 CART DNA: Updating PUBLIC address from {:?} to: {} {}, {}", 
-           previous_address_hash, new_address.street, new_address.city, new_address.state);
+    warn!("🔄 CART DNA: Updating encrypted address from {:?}", previous_address_hash);
     
-    // Find and delete the link pointing to the previous address
+    // Find and delete the link pointing to the previous address (legacy public or encrypted)
     let links = get_links(
         GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
     )?;
@@ -358,10 +464,11 @@ pub(crate) fn update_delivery_address_impl(previous_address_hash: ActionHash, ne
         }
     }
     
-    // Create new address entry
-    let new_address_hash = create_entry(EntryTypes::Address(new_address))?;
+    // Create new sealed address entry
+    let sealed = seal_address(&new_address)?;
+    let new_address_hash = create_entry(EntryTypes::EncryptedDeliveryData(sealed))?;
     
-    warn!("✅ CART DNA: Updated public address created with hash: {:?}", new_address_hash);
+    warn!("✅ CART DNA: Updated encrypted address created with hash: {:?}", new_address_hash);
     
     // Link new address to PUBLIC path
     create_link(
@@ -374,17 +481,13 @@ pub(crate) fn update_delivery_address_impl(previous_address_hash: ActionHash, ne
     Ok(new_address_hash)
 }
 
-// Set delivery time slot - create_entry + create_link to PUBLIC path
-pub(crate) fn set_delivery_time_slot_impl(time_slot: DeliveryTimeSlot) -> ExternResult<ActionHash> {
+// Unlink the current delivery time slot (if any) so it no longer holds a place in that window
+pub(crate) fn release_delivery_time_slot() -> ExternResult<()> {
     let public_path = get_public_cart_path()?;
     let public_hash = public_path.path_entry_hash()?;
     
-    warn!("🛒 CART DNA: Creating PUBLIC delivery time slot entry: {} at {}", 
-           time_slot.time_slot, time_slot.date);
-    
-    // Check if time slot already exists and delete old link
     let links = get_links(
-        GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
+        GetLinksInputBuilder::try_new(public_hash, LinkTypes::PublicPathToCartData)?.build()
     )?;
     for link in links {
         if let Some(target_hash) = link.target.clone().into_action_hash() {
@@ -396,6 +499,19 @@ pub(crate) fn set_delivery_time_slot_impl(time_slot: DeliveryTimeSlot) -> Extern
             }
         }
     }
+    Ok(())
+}
+
+// Set delivery time slot - create_entry + create_link to PUBLIC path
+pub(crate) fn set_delivery_time_slot_impl(time_slot: DeliveryTimeSlot) -> ExternResult<ActionHash> {
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+    
+    warn!("🛒 CART DNA: Creating PUBLIC delivery time slot entry: {} at {}", 
+           time_slot.time_slot, time_slot.date);
+    
+    // Check if time slot already exists and delete old link
+    release_delivery_time_slot()?;
     
     // Create the DeliveryTimeSlot entry
     let time_slot_hash = create_entry(EntryTypes::DeliveryTimeSlot(time_slot))?;
@@ -413,22 +529,25 @@ pub(crate) fn set_delivery_time_slot_impl(time_slot: DeliveryTimeSlot) -> Extern
     Ok(time_slot_hash)
 }
 
-// Set delivery instructions - create_entry + create_link to PUBLIC path
+// Set delivery instructions - sealed to the customer (and assigned shopper) + create_link to PUBLIC path
 pub(crate) fn set_delivery_instructions_impl(instructions: DeliveryInstructions) -> ExternResult<ActionHash> {
     let public_path = get_public_cart_path()?;
     let public_hash = public_path.path_entry_hash()?;
     
-    warn!("🛒 CART DNA: Creating PUBLIC delivery instructions entry: {}", 
-           instructions.instructions);
+    warn!("🛒 CART DNA: Creating encrypted delivery instructions entry");
     
-    // Check if instructions already exist and delete old link
+    // Check if instructions already exist (legacy public or encrypted) and delete old link
     let links = get_links(
         GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
     )?;
     for link in links {
         if let Some(target_hash) = link.target.clone().into_action_hash() {
             if let Some(record) = get(target_hash, GetOptions::default())? {
-                if DeliveryInstructions::try_from(record).is_ok() {
+                let is_instructions = DeliveryInstructions::try_from(record.clone()).is_ok()
+                    || EncryptedDeliveryData::try_from(record)
+                        .map(|data| data.kind == DeliveryDataKind::Instructions)
+                        .unwrap_or(false);
+                if is_instructions {
                     delete_link(link.create_link_hash)?;
                     break;
                 }
@@ -436,12 +555,13 @@ pub(crate) fn set_delivery_instructions_impl(instructions: DeliveryInstructions)
         }
     }
     
-    // Create the DeliveryInstructions entry
-    let instructions_hash = create_entry(EntryTypes::DeliveryInstructions(instructions))?;
+    // Create the sealed DeliveryInstructions entry
+    let sealed = seal_delivery_data(DeliveryDataKind::Instructions, &instructions)?;
+    let instructions_hash = create_entry(EntryTypes::EncryptedDeliveryData(sealed))?;
     
-    warn!("✅ CART DNA: Public delivery instructions created with hash: {:?}", instructions_hash);
+    warn!("✅ CART DNA: Encrypted delivery instructions created with hash: {:?}", instructions_hash);
     
-    // Link it to the PUBLIC path so all agents can see it
+    // Link it to the PUBLIC path - the envelope is public, the content is not
     create_link(
         public_hash,
         instructions_hash.clone(),
@@ -453,15 +573,23 @@ pub(crate) fn set_delivery_instructions_impl(instructions: DeliveryInstructions)
 }
 
 // Consolidated function to get all session data in one call using PUBLIC path - ALL DATA IS PUBLIC
-pub(crate) fn get_session_data_impl() -> ExternResult<CartSessionData> {
-    warn!("🔍 GET SESSION DATA: Starting get_session_data_impl");
+// local_only reads links and records from this node's store without going to the network
+pub(crate) fn get_session_data_impl(local_only: bool) -> ExternResult<CartSessionData> {
+    warn!("🔍 GET SESSION DATA: Starting get_session_data_impl (local only: {})", local_only);
     
     let public_path = get_public_cart_path()?;
     let public_hash = public_path.path_entry_hash()?;
+    let (strategy, get_options) = if local_only {
+        (GetStrategy::Local, GetOptions::local())
+    } else {
+        (GetStrategy::Network, GetOptions::network())
+    };
     
     // Get all links from PUBLIC path - ALL ENTRIES ARE PUBLIC
     let all_links = get_links(
-        GetLinksInputBuilder::try_new(public_hash, LinkTypes::PublicPathToCartData)?.build()
+        GetLinksInputBuilder::try_new(public_hash, LinkTypes::PublicPathToCartData)?
+            .get_options(strategy)
+            .build()
     )?;
     
     // Process all links and categorize by entry type
@@ -470,10 +598,15 @@ pub(crate) fn get_session_data_impl() -> ExternResult<CartSessionData> {
     let mut address = None;
     let mut delivery_time_slot = None;
     let mut delivery_instructions = None;
+    let mut decrypted_address = None;
+    let mut decrypted_instructions = None;
+    let mut gift_details = None;
+    let mut decrypted_gift = None;
+    let mut fulfillment = None;
     
     for link in all_links {
         if let Some(target_hash) = link.target.into_action_hash() {
-            if let Some(record) = get(target_hash.clone(), GetOptions::default())? {
+            if let Some(record) = get(target_hash.clone(), get_options.clone())? {
                 // Try to parse based on entry content using .is_ok() to avoid crashes
                 if let Ok(cart_product) = CartProduct::try_from(record.clone()) {
                     // Read quantity and timestamp from link tag
@@ -490,12 +623,33 @@ pub(crate) fn get_session_data_impl() -> ExternResult<CartSessionData> {
                     }
                 } else if SessionStatus::try_from(record.clone()).is_ok() {
                     session_status = Some(record);
-                } else if Address::try_from(record.clone()).is_ok() {
+                } else if let Ok(sealed) = EncryptedDeliveryData::try_from(record.clone()) {
+                    // Decrypt transparently when the caller holds an envelope
+                    match sealed.kind {
+                        DeliveryDataKind::Address => {
+                            decrypted_address = open_delivery_data(&sealed)?;
+                            address = Some(record);
+                        }
+                        DeliveryDataKind::Instructions => {
+                            decrypted_instructions = open_delivery_data(&sealed)?;
+                            delivery_instructions = Some(record);
+                        }
+                        DeliveryDataKind::Gift => {
+                            decrypted_gift = open_delivery_data(&sealed)?;
+                            gift_details = Some(record);
+                        }
+                    }
+                } else if let Ok(legacy_address) = Address::try_from(record.clone()) {
+                    // Legacy plaintext entry written before encryption
+                    decrypted_address = Some(legacy_address);
                     address = Some(record);
                 } else if DeliveryTimeSlot::try_from(record.clone()).is_ok() {
                     delivery_time_slot = Some(record);
-                } else if DeliveryInstructions::try_from(record.clone()).is_ok() {
+                } else if let Ok(legacy_instructions) = DeliveryInstructions::try_from(record.clone()) {
+                    decrypted_instructions = Some(legacy_instructions);
                     delivery_instructions = Some(record);
+                } else if let Ok(order_fulfillment) = OrderFulfillment::try_from(record.clone()) {
+                    fulfillment = Some(order_fulfillment);
                 }
             }
         }
@@ -511,6 +665,13 @@ pub(crate) fn get_session_data_impl() -> ExternResult<CartSessionData> {
         address,
         delivery_time_slot,
         delivery_instructions,
+        decrypted_address,
+        decrypted_instructions,
+        gift_details,
+        decrypted_gift,
+        fulfillment,
+        local_only,
+        pending_ops: crate::offline::get_pending_cart_ops_impl()?,
     })
 }
 
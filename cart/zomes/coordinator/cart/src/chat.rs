@@ -0,0 +1,168 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::encryption::{get_agent_encryption_key, open_envelopes, seal_for_recipients};
+use crate::order::require_order_assignment;
+use crate::signals::{notify_agents, RemoteCartSignal};
+
+// A chat message as seen by one of the two parties
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderChatMessage {
+    pub message_hash: ActionHash,
+    pub author: AgentPubKey,
+    pub content: Option<ChatMessageContent>, // None if the caller cannot open it
+    pub sent_at: u64,
+    pub read_at: Option<u64>,                // When the recipient read it
+}
+
+// Helper to require that the caller is a party of the claimed order, returning the other party
+fn require_chat_party() -> ExternResult<(ActionHash, AgentPubKey)> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let (assignment_hash, assignment) = require_order_assignment()?;
+
+    if me == assignment.customer {
+        Ok((assignment_hash, assignment.shopper))
+    } else if me == assignment.shopper {
+        Ok((assignment_hash, assignment.customer))
+    } else {
+        Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer and shopper of this order can use its chat".to_string()
+        )))
+    }
+}
+
+// Send a text (or quick reply) message to the other party of the order
+pub(crate) fn send_order_message_impl(text: String, quick_reply: Option<QuickReply>) -> ExternResult<ActionHash> {
+    let (assignment_hash, other_party) = require_chat_party()?;
+    let me = agent_info()?.agent_initial_pubkey;
+
+    let text = match (&quick_reply, text.trim().is_empty()) {
+        (Some(reply), true) => reply.default_text().to_string(),
+        _ => text,
+    };
+    if text.trim().is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest("Message cannot be empty".to_string())));
+    }
+    if text.len() > MAX_CHAT_MESSAGE_LENGTH {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Message cannot exceed {} characters", MAX_CHAT_MESSAGE_LENGTH
+        ))));
+    }
+
+    let my_key = crate::encryption::ensure_encryption_key()?;
+    let other_key = get_agent_encryption_key(other_party.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "The other party has not published an encryption key".to_string()
+        )))?;
+
+    let content = ChatMessageContent { text, quick_reply };
+    let (sender_key, envelopes) = seal_for_recipients(
+        vec![(me, my_key), (other_party.clone(), other_key)],
+        &content,
+    )?;
+
+    let message = OrderMessage {
+        assignment_hash: assignment_hash.clone(),
+        recipient: other_party.clone(),
+        sender_key,
+        envelopes,
+        sent_at: sys_time()?.as_micros() as u64,
+    };
+
+    let message_hash = create_entry(EntryTypes::OrderMessage(message.clone()))?;
+    create_link(assignment_hash, message_hash.clone(), LinkTypes::AssignmentToMessage, ())?;
+
+    warn!("💬 CHAT: Message {:?} sent to {:?}", message_hash, other_party);
+
+    notify_agents(
+        RemoteCartSignal::ChatMessage {
+            message_hash: message_hash.clone(),
+            message,
+        },
+        vec![other_party],
+    );
+
+    Ok(message_hash)
+}
+
+// Read the earliest read-receipt timestamp the recipient left on a message
+fn get_read_at(message_hash: &ActionHash, recipient: &AgentPubKey) -> ExternResult<Option<u64>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(message_hash.clone(), LinkTypes::MessageToReader)?.build()
+    )?;
+
+    Ok(links
+        .into_iter()
+        .filter(|link| link.author == *recipient)
+        .map(|link| {
+            let bytes: [u8; 8] = link.tag.0.get(0..8).and_then(|b| b.try_into().ok()).unwrap_or([0; 8]);
+            u64::from_le_bytes(bytes)
+        })
+        .min())
+}
+
+// Get the whole thread, oldest first, opening each message for the caller
+pub(crate) fn get_order_messages_impl() -> ExternResult<Vec<OrderChatMessage>> {
+    let (assignment_hash, _) = require_chat_party()?;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(assignment_hash, LinkTypes::AssignmentToMessage)?.build()
+    )?;
+
+    let mut messages = Vec::new();
+    for link in links {
+        if let Some(message_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(message_hash.clone(), GetOptions::default())? {
+                let author = record.action().author().clone();
+                if let Ok(message) = OrderMessage::try_from(record) {
+                    let content = open_envelopes(&message.sender_key, &message.envelopes)?;
+                    let read_at = get_read_at(&message_hash, &message.recipient)?;
+                    messages.push(OrderChatMessage {
+                        message_hash,
+                        author,
+                        content,
+                        sent_at: message.sent_at,
+                        read_at,
+                    });
+                }
+            }
+        }
+    }
+
+    messages.sort_by_key(|message| message.sent_at);
+    Ok(messages)
+}
+
+// Leave read receipts on every unread message addressed to the caller
+pub(crate) fn mark_messages_read_impl() -> ExternResult<Vec<ActionHash>> {
+    let (_, other_party) = require_chat_party()?;
+    let me = agent_info()?.agent_initial_pubkey;
+    let read_at = sys_time()?.as_micros() as u64;
+
+    let mut newly_read = Vec::new();
+    for message in get_order_messages_impl()? {
+        if message.author == me || message.read_at.is_some() {
+            continue;
+        }
+        create_link(
+            message.message_hash.clone(),
+            me.clone(),
+            LinkTypes::MessageToReader,
+            LinkTag::new(read_at.to_le_bytes().to_vec()),
+        )?;
+        newly_read.push(message.message_hash);
+    }
+
+    if !newly_read.is_empty() {
+        notify_agents(
+            RemoteCartSignal::MessagesRead {
+                message_hashes: newly_read.clone(),
+                read_at,
+            },
+            vec![other_party],
+        );
+    }
+
+    Ok(newly_read)
+}
@@ -0,0 +1,118 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::{find_public_record, get_current_items_impl};
+use crate::delivery_proof::get_delivery_address_for_caller;
+use crate::fulfillment::get_order_fulfillment_impl;
+use crate::picking::estimated_cart_total;
+use crate::properties::get_cart_properties;
+
+// Something that must be fixed before the order can be published
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum CheckoutProblem {
+    EmptyCart,
+    MissingAddress,
+    MissingTimeSlot,
+    TimeSlotInPast { date: u64 },
+    BelowMinimumBasket { minimum: Money, total: Money, shortfall: Money },
+    OutOfDeliveryZone { distance_meters: f64, radius_meters: f64 },
+    PickupUnavailable { store_id: String },
+    AgeConfirmationRequired { product_names: Vec<String> },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CheckoutValidation {
+    pub ready: bool,
+    pub problems: Vec<CheckoutProblem>,
+}
+
+// Collect every blocking problem with the current cart session - writes nothing
+pub(crate) fn validate_checkout_impl(age_confirmed: bool) -> ExternResult<CheckoutValidation> {
+    let properties = get_cart_properties()?;
+    let items = get_current_items_impl()?;
+    let mut problems = Vec::new();
+
+    if items.is_empty() {
+        problems.push(CheckoutProblem::EmptyCart);
+    } else if let Some(minimum) = &properties.minimum_basket {
+        let total = estimated_cart_total(&items)?;
+        if total.currency == minimum.currency && total.amount_minor < minimum.amount_minor {
+            problems.push(CheckoutProblem::BelowMinimumBasket {
+                shortfall: Money::new(minimum.amount_minor - total.amount_minor, &total.currency),
+                minimum: minimum.clone(),
+                total,
+            });
+        }
+    }
+
+    // Pickup orders name a store instead of an address and skip the delivery zone
+    let pickup_store = match get_order_fulfillment_impl()? {
+        Some((_, OrderFulfillment { mode: FulfillmentMode::Pickup, store: Some(store) })) => Some(store),
+        _ => None,
+    };
+    match pickup_store {
+        Some(store) => {
+            if let Some(pickup_locations) = &properties.pickup_locations {
+                if !pickup_locations.iter().any(|location| location.store_id == store.store_id) {
+                    problems.push(CheckoutProblem::PickupUnavailable { store_id: store.store_id });
+                }
+            }
+        }
+        None => match get_delivery_address_for_caller()? {
+            None => problems.push(CheckoutProblem::MissingAddress),
+            Some((_, address)) => {
+                // A sealed address the caller cannot open is left to the customer's own check
+                if let (Some(address), Some(zone)) = (address, &properties.delivery_zone) {
+                    let distance = distance_meters(zone.center_lat, zone.center_lng, address.lat, address.lng);
+                    if distance > zone.radius_meters {
+                        problems.push(CheckoutProblem::OutOfDeliveryZone {
+                            distance_meters: distance,
+                            radius_meters: zone.radius_meters,
+                        });
+                    }
+                }
+            }
+        },
+    }
+
+    // Slot dates are the UI's millisecond timestamps for the start of the delivery (or pickup) day
+    let now = sys_time()?.as_millis() as u64;
+    match find_public_record::<DeliveryTimeSlot>()? {
+        None => problems.push(CheckoutProblem::MissingTimeSlot),
+        Some((_, time_slot)) if time_slot.date.saturating_add(MILLIS_PER_DAY) <= now => {
+            problems.push(CheckoutProblem::TimeSlotInPast { date: time_slot.date });
+        }
+        Some(_) => {}
+    }
+
+    if !age_confirmed {
+        let product_names: Vec<String> = items
+            .iter()
+            .filter(|item| item.product.age_restricted)
+            .map(|item| item.product.product_name.clone())
+            .collect();
+        if !product_names.is_empty() {
+            problems.push(CheckoutProblem::AgeConfirmationRequired { product_names });
+        }
+    }
+
+    warn!("🧾 CHECKOUT: {} blocking problems", problems.len());
+    Ok(CheckoutValidation {
+        ready: problems.is_empty(),
+        problems,
+    })
+}
+
+// Refuse with the full problem list so the UI can show everything at once
+pub(crate) fn require_valid_checkout(age_confirmed: bool) -> ExternResult<()> {
+    let validation = validate_checkout_impl(age_confirmed)?;
+    if !validation.ready {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Order cannot be published yet: {:?}",
+            validation.problems
+        ))));
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::{cart_contains_restricted_items, find_public_record, get_public_cart_path};
+use crate::encryption::{find_encrypted_delivery_data, open_delivery_data};
+use crate::fulfillment::get_order_fulfillment_impl;
+use crate::picking::fulfillment_total;
+
+// Everything the shopper app captures at the door
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryProofInput {
+    pub photo_content_hash: Vec<u8>,    // 32-byte blake2b of the whole photo
+    pub photo_chunks: Vec<ActionHash>,   // From upload_delivery_photo_chunk, in order
+    pub photo_url: Option<String>,       // When the photo is stored externally
+    pub lat: f64,
+    pub lng: f64,
+    pub recipient_name: Option<String>,
+    // Required when the order contains age-restricted items
+    #[serde(default)]
+    pub id_check: Option<IdCheckInput>,
+}
+
+// What the shopper records after checking the recipient's ID
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IdCheckInput {
+    pub document_type: String,
+    pub recipient_meets_minimum_age: bool,
+}
+
+// Store one chunk of the delivery photo
+pub(crate) fn upload_delivery_photo_chunk_impl(chunk_index: u32, bytes: Vec<u8>) -> ExternResult<ActionHash> {
+    if bytes.len() > PHOTO_CHUNK_SIZE {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Photo chunks cannot exceed {} bytes", PHOTO_CHUNK_SIZE
+        ))));
+    }
+    create_entry(EntryTypes::PhotoChunk(PhotoChunk { chunk_index, bytes }))
+}
+
+// Find the customer's delivery address record and open it for the caller when possible
+pub(crate) fn get_delivery_address_for_caller() -> ExternResult<Option<(ActionHash, Option<Address>)>> {
+    if let Some((record, sealed)) = find_encrypted_delivery_data(DeliveryDataKind::Address)? {
+        let address: Option<Address> = open_delivery_data(&sealed)?;
+        return Ok(Some((record.action_address().clone(), address)));
+    }
+    if let Some((record, address)) = find_public_record::<Address>()? {
+        return Ok(Some((record.action_address().clone(), Some(address))));
+    }
+    Ok(None)
+}
+
+// The record the order is handed over at - the pickup choice for pickup orders, otherwise the delivery address
+pub(crate) fn get_handover_hash() -> ExternResult<Option<ActionHash>> {
+    if let Some((record, OrderFulfillment { mode: FulfillmentMode::Pickup, store: Some(_) })) =
+        get_order_fulfillment_impl()?
+    {
+        return Ok(Some(record.action_address().clone()));
+    }
+    Ok(get_delivery_address_for_caller()?.map(|(address_hash, _)| address_hash))
+}
+
+// The Checkout status the shopper claimed
+pub(crate) fn get_published_status(assignment: &OrderAssignment) -> ExternResult<SessionStatus> {
+    let record = get(assignment.published_status_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Published order status not found".to_string())))?;
+    SessionStatus::try_from(record)
+}
+
+// Create the signed DeliveryProof for the claimed order
+pub(crate) fn create_delivery_proof(
+    assignment_hash: ActionHash,
+    assignment: &OrderAssignment,
+    input: DeliveryProofInput,
+) -> ExternResult<ActionHash> {
+    let me = agent_info()?.agent_initial_pubkey;
+
+    // The published flag is the customer's word - the lines in the cart right now are checked as well
+    let published_status = get_published_status(assignment)?;
+    let contains_restricted_items = published_status.contains_restricted_items || cart_contains_restricted_items()?;
+    let age_verification = match input.id_check {
+        Some(id_check) => {
+            if !id_check.recipient_meets_minimum_age {
+                return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                    "Age-restricted items cannot be handed to a recipient under {}", RESTRICTED_ITEMS_MINIMUM_AGE
+                ))));
+            }
+            Some(AgeVerification {
+                document_type: id_check.document_type,
+                minimum_age: RESTRICTED_ITEMS_MINIMUM_AGE,
+                recipient_meets_minimum_age: true,
+                checked_at: sys_time()?.as_micros() as u64,
+            })
+        }
+        None if contains_restricted_items => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "This order contains age-restricted items - check the recipient's ID before delivering".to_string()
+            )));
+        }
+        None => None,
+    };
+
+    if !is_valid_coordinate(input.lat, input.lng) {
+        return Err(wasm_error!(WasmErrorInner::Guest("Delivery coordinates are out of range".to_string())));
+    }
+
+    // Pickup orders are handed over at the store named in the fulfillment choice
+    let (address_hash, lat, lng, address_opening) = match get_order_fulfillment_impl()? {
+        Some((record, OrderFulfillment { mode: FulfillmentMode::Pickup, store: Some(store) })) => {
+            (record.action_address().clone(), store.lat, store.lng, None)
+        }
+        _ => {
+            let (address_hash, address) = get_delivery_address_for_caller()?
+                .ok_or(wasm_error!(WasmErrorInner::Guest("Order has no delivery address".to_string())))?;
+
+            let address = address.ok_or(wasm_error!(WasmErrorInner::Guest(
+                "Delivery address has not been shared with the shopper yet".to_string()
+            )))?;
+            // Sealed addresses are invisible to validators - reveal only the committed cell so they can check the distance
+            let address_opening = address.location_salt.map(|salt| AddressLocationOpening {
+                lat: coarsen_coordinate(address.lat),
+                lng: coarsen_coordinate(address.lng),
+                salt,
+            });
+            (address_hash, address.lat, address.lng, address_opening)
+        }
+    };
+    let distance = distance_meters(input.lat, input.lng, lat, lng);
+    if distance > DELIVERY_PROOF_MAX_DISTANCE_METERS {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "You are {:.0}m from the handover point (limit {:.0}m)",
+            distance, DELIVERY_PROOF_MAX_DISTANCE_METERS
+        ))));
+    }
+
+    // Paid orders capture what the picks reconcile to
+    let delivered_total = match published_status.payment_intent_hash {
+        Some(_) => Some(fulfillment_total()?),
+        None => None,
+    };
+
+    let order_signature = sign_raw(me, assignment_hash.get_raw_39().to_vec())?;
+
+    let proof = DeliveryProof {
+        assignment_hash,
+        address_hash,
+        photo_content_hash: input.photo_content_hash,
+        photo_chunks: input.photo_chunks,
+        photo_url: input.photo_url,
+        lat: coarsen_coordinate(input.lat),
+        lng: coarsen_coordinate(input.lng),
+        delivered_at: sys_time()?.as_micros() as u64,
+        recipient_name: input.recipient_name,
+        order_signature,
+        age_verification,
+        contains_restricted_items,
+        delivered_total,
+        address_opening,
+    };
+
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    let proof_hash = create_entry(EntryTypes::DeliveryProof(proof))?;
+    create_link(public_hash, proof_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
+
+    warn!("📸 DELIVERY PROOF: Created {:?} {:.0}m from the handover point", proof_hash, distance);
+    Ok(proof_hash)
+}
+
+// Get the delivery proof for this order, if one was submitted
+pub(crate) fn get_delivery_proof_impl() -> ExternResult<Option<Record>> {
+    Ok(find_public_record::<DeliveryProof>()?.map(|(record, _)| record))
+}
+
+// Reassemble a chunked delivery photo
+pub(crate) fn get_delivery_photo_impl() -> ExternResult<Option<Vec<u8>>> {
+    let proof = match find_public_record::<DeliveryProof>()? {
+        Some((_, proof)) => proof,
+        None => return Ok(None),
+    };
+    if proof.photo_chunks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut chunks = Vec::new();
+    for chunk_hash in proof.photo_chunks {
+        let record = get(chunk_hash, GetOptions::default())?
+            .ok_or(wasm_error!(WasmErrorInner::Guest("Photo chunk not found".to_string())))?;
+        chunks.push(PhotoChunk::try_from(record)?);
+    }
+    chunks.sort_by_key(|chunk| chunk.chunk_index);
+
+    Ok(Some(chunks.into_iter().flat_map(|chunk| chunk.bytes).collect()))
+}
@@ -0,0 +1,265 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::cart::get_public_cart_path;
+use crate::order::get_order_assignment_impl;
+
+// Get this agent's X25519 key from the local chain, creating and publishing one on first use
+pub(crate) fn ensure_encryption_key() -> ExternResult<X25519PubKey> {
+    if let Some(key) = get_own_encryption_key()? {
+        return Ok(key);
+    }
+
+    let agent = agent_info()?.agent_initial_pubkey;
+    let x25519_pub_key = create_x25519_keypair()?;
+    let key_entry = AgentEncryptionKey {
+        x25519_pub_key,
+        created_at: sys_time()?.as_micros() as u64,
+    };
+
+    warn!("🔐 ENCRYPTION: Publishing X25519 key for {:?}", agent);
+
+    let key_hash = create_entry(EntryTypes::AgentEncryptionKey(key_entry))?;
+    create_link(agent, key_hash, LinkTypes::AgentToEncryptionKey, ())?;
+
+    Ok(x25519_pub_key)
+}
+
+// Read this agent's own X25519 key from its source chain
+fn get_own_encryption_key() -> ExternResult<Option<X25519PubKey>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::AgentEncryptionKey.try_into()?)
+            .include_entries(true),
+    )?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| AgentEncryptionKey::try_from(record).ok())
+        .last()
+        .map(|key| key.x25519_pub_key))
+}
+
+// Look up another agent's published X25519 key
+pub(crate) fn get_agent_encryption_key(agent: AgentPubKey) -> ExternResult<Option<X25519PubKey>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent, LinkTypes::AgentToEncryptionKey)?.build()
+    )?;
+
+    let mut latest: Option<AgentEncryptionKey> = None;
+    for link in links {
+        if let Some(target_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(target_hash, GetOptions::default())? {
+                if let Ok(key) = AgentEncryptionKey::try_from(record) {
+                    if latest.as_ref().is_none_or(|current| key.created_at >= current.created_at) {
+                        latest = Some(key);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(latest.map(|key| key.x25519_pub_key))
+}
+
+// Box a serializable value once per recipient, returning the sender key and the envelopes
+pub(crate) fn seal_for_recipients<T: Serialize + std::fmt::Debug>(
+    recipients: Vec<(AgentPubKey, X25519PubKey)>,
+    value: &T,
+) -> ExternResult<(X25519PubKey, Vec<SealedEnvelope>)> {
+    let sender_key = ensure_encryption_key()?;
+    let plaintext = encode(value)
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Failed to encode sealed data: {:?}", e))))?;
+
+    let mut envelopes = Vec::new();
+    for (recipient, recipient_key) in recipients {
+        let sealed = x_25519_x_salsa20_poly1305_encrypt(
+            sender_key,
+            recipient_key,
+            XSalsa20Poly1305Data::from(plaintext.clone()),
+        )?;
+        envelopes.push(SealedEnvelope {
+            recipient,
+            recipient_key,
+            sealed,
+        });
+    }
+
+    Ok((sender_key, envelopes))
+}
+
+// Open the caller's envelope - None when the caller is not a recipient
+pub(crate) fn open_envelopes<T: serde::de::DeserializeOwned + std::fmt::Debug>(
+    sender_key: &X25519PubKey,
+    envelopes: &[SealedEnvelope],
+) -> ExternResult<Option<T>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let envelope = match envelopes.iter().find(|envelope| envelope.recipient == me) {
+        Some(envelope) => envelope,
+        None => return Ok(None),
+    };
+
+    let opened = x_25519_x_salsa20_poly1305_decrypt(
+        envelope.recipient_key,
+        *sender_key,
+        envelope.sealed.clone(),
+    )?;
+
+    match opened {
+        Some(plaintext) => {
+            let value = decode(plaintext.as_ref())
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Failed to decode sealed data: {:?}", e))))?;
+            Ok(Some(value))
+        }
+        None => Ok(None),
+    }
+}
+
+// Seal a serializable value for the customer and, once the order is claimed, the assigned shopper
+pub(crate) fn seal_delivery_data<T: Serialize + std::fmt::Debug>(
+    kind: DeliveryDataKind,
+    value: &T,
+) -> ExternResult<EncryptedDeliveryData> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let my_key = ensure_encryption_key()?;
+
+    let mut recipients = vec![(me.clone(), my_key)];
+    let mut assignment_hash = None;
+    if let Some((record, assignment)) = get_order_assignment_impl()? {
+        if assignment.customer == me {
+            if let Some(shopper_key) = get_agent_encryption_key(assignment.shopper.clone())? {
+                recipients.push((assignment.shopper, shopper_key));
+                assignment_hash = Some(record.action_address().clone());
+            }
+        }
+    }
+
+    let (sender_key, envelopes) = seal_for_recipients(recipients, value)?;
+
+    Ok(EncryptedDeliveryData {
+        kind,
+        sender_key,
+        assignment_hash,
+        envelopes,
+        sealed_at: sys_time()?.as_micros() as u64,
+        location_commitment: None,
+    })
+}
+
+// Seal an Address with a commitment to its trail-precision cell - the salt travels inside the envelope,
+// so re-sealing keeps the same commitment and the shopper can reveal the cell in the delivery proof
+pub(crate) fn seal_address(address: &Address) -> ExternResult<EncryptedDeliveryData> {
+    let mut address = address.clone();
+    let salt = match address.location_salt.clone() {
+        Some(salt) => salt,
+        None => random_bytes(LOCATION_COMMITMENT_LENGTH as u32)?.into_vec(),
+    };
+    address.location_salt = Some(salt.clone());
+
+    let mut sealed = seal_delivery_data(DeliveryDataKind::Address, &address)?;
+    sealed.location_commitment = Some(location_commitment(
+        coarsen_coordinate(address.lat),
+        coarsen_coordinate(address.lng),
+        &salt,
+    )?);
+    Ok(sealed)
+}
+
+// Open the caller's delivery data envelope - None when the caller is not a recipient
+pub(crate) fn open_delivery_data<T: serde::de::DeserializeOwned + std::fmt::Debug>(
+    data: &EncryptedDeliveryData,
+) -> ExternResult<Option<T>> {
+    open_envelopes(&data.sender_key, &data.envelopes)
+}
+
+// Find the current sealed entry of a given kind on the PUBLIC path
+pub(crate) fn find_encrypted_delivery_data(kind: DeliveryDataKind) -> ExternResult<Option<(Record, EncryptedDeliveryData)>> {
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(public_hash, LinkTypes::PublicPathToCartData)?.build()
+    )?;
+
+    for link in links {
+        if let Some(target_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(target_hash, GetOptions::default())? {
+                if let Ok(data) = EncryptedDeliveryData::try_from(record.clone()) {
+                    if data.kind == kind {
+                        return Ok(Some((record, data)));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// Customer re-seals address, instructions and gift details so the assigned shopper can open them
+pub(crate) fn share_delivery_details_impl() -> ExternResult<Vec<ActionHash>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let (_, assignment) = get_order_assignment_impl()?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Order has not been claimed".to_string())))?;
+    if assignment.customer != me {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer can share delivery details".to_string()
+        )));
+    }
+    if get_agent_encryption_key(assignment.shopper.clone())?.is_none() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Assigned shopper has not published an encryption key".to_string()
+        )));
+    }
+
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+    let mut resealed = Vec::new();
+
+    for kind in [DeliveryDataKind::Address, DeliveryDataKind::Instructions, DeliveryDataKind::Gift] {
+        let (record, data) = match find_encrypted_delivery_data(kind.clone())? {
+            Some(found) => found,
+            None => continue,
+        };
+        if data.envelopes.iter().any(|envelope| envelope.recipient == assignment.shopper) {
+            continue;
+        }
+
+        let new_data = match kind {
+            DeliveryDataKind::Address => {
+                let address: Address = open_delivery_data(&data)?
+                    .ok_or(wasm_error!(WasmErrorInner::Guest("Failed to open delivery address".to_string())))?;
+                seal_address(&address)?
+            }
+            DeliveryDataKind::Instructions => {
+                let instructions: DeliveryInstructions = open_delivery_data(&data)?
+                    .ok_or(wasm_error!(WasmErrorInner::Guest("Failed to open delivery instructions".to_string())))?;
+                seal_delivery_data(DeliveryDataKind::Instructions, &instructions)?
+            }
+            DeliveryDataKind::Gift => {
+                let gift: GiftDetails = open_delivery_data(&data)?
+                    .ok_or(wasm_error!(WasmErrorInner::Guest("Failed to open gift details".to_string())))?;
+                seal_delivery_data(DeliveryDataKind::Gift, &gift)?
+            }
+        };
+
+        let new_hash = update_entry(record.action_address().clone(), new_data)?;
+
+        // Move the public path link to the re-sealed version
+        let links = get_links(
+            GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
+        )?;
+        for link in links {
+            if link.target.clone().into_action_hash().as_ref() == Some(record.action_address()) {
+                delete_link(link.create_link_hash)?;
+                break;
+            }
+        }
+        create_link(public_hash.clone(), new_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
+
+        resealed.push(new_hash);
+    }
+
+    warn!("🔐 ENCRYPTION: Shared {} delivery entries with the assigned shopper", resealed.len());
+    Ok(resealed)
+}
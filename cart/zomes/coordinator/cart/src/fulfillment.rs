@@ -0,0 +1,115 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::cart::{current_status, find_public_record, get_public_cart_path, write_session_status};
+use crate::order::require_order_assignment;
+use crate::properties::get_cart_properties;
+use crate::signals::{notify_agents, RemoteCartSignal};
+
+// The session's fulfillment choice - sessions that never chose are delivered
+pub(crate) fn get_order_fulfillment_impl() -> ExternResult<Option<(Record, OrderFulfillment)>> {
+    find_public_record::<OrderFulfillment>()
+}
+
+pub(crate) fn is_pickup_order() -> ExternResult<bool> {
+    Ok(get_order_fulfillment_impl()?.is_some_and(|(_, fulfillment)| fulfillment.mode == FulfillmentMode::Pickup))
+}
+
+// Choose delivery or pickup while the cart is still being built - replaces any earlier choice
+pub(crate) fn set_fulfillment_mode_impl(mode: FulfillmentMode, store: Option<StoreLocation>) -> ExternResult<ActionHash> {
+    if let Some(status) = current_status()? {
+        if status != "Shopping" {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Fulfillment can only be changed while shopping (status is {})", status
+            ))));
+        }
+    }
+    if let (Some(store), Some(pickup_locations)) = (&store, get_cart_properties()?.pickup_locations) {
+        if !pickup_locations.iter().any(|location| location.store_id == store.store_id) {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Store {} does not offer pickup", store.store_id
+            ))));
+        }
+    }
+
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    // Unlink the previous choice
+    let links = get_links(
+        GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
+    )?;
+    for link in links {
+        if let Some(target_hash) = link.target.clone().into_action_hash() {
+            if let Some(record) = get(target_hash, GetOptions::default())? {
+                if OrderFulfillment::try_from(record).is_ok() {
+                    delete_link(link.create_link_hash)?;
+                }
+            }
+        }
+    }
+
+    warn!("🏬 FULFILLMENT: Switching to {:?} {:?}", mode, store.as_ref().map(|store| &store.store_id));
+    let fulfillment_hash = create_entry(EntryTypes::OrderFulfillment(OrderFulfillment { mode, store }))?;
+    create_link(public_hash, fulfillment_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
+    Ok(fulfillment_hash)
+}
+
+// Assigned shopper has bagged a pickup order - the customer is told to come in
+pub(crate) fn mark_ready_for_pickup_impl() -> ExternResult<ActionHash> {
+    let (_, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can mark the order ready".to_string()
+        )));
+    }
+    let store = match get_order_fulfillment_impl()? {
+        Some((_, OrderFulfillment { mode: FulfillmentMode::Pickup, store: Some(store) })) => store,
+        _ => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Only pickup orders can be marked ready for pickup".to_string()
+            )));
+        }
+    };
+    let status = current_status()?.unwrap_or_else(|| "Shopping".to_string());
+    if !is_allowed_status_transition(&status, "ReadyForPickup") {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "An order in {} cannot be marked ready for pickup", status
+        ))));
+    }
+
+    let status_hash = write_session_status("ReadyForPickup")?;
+    warn!("🏬 FULFILLMENT: Order ready for pickup at {}", store.name);
+    notify_agents(RemoteCartSignal::OrderReadyForPickup { store }, vec![assignment.customer]);
+    Ok(status_hash)
+}
+
+// Customer has arrived at the store ("I'm here, bay 3") - only the shopper is told, nothing is written
+pub(crate) fn check_in_for_pickup_impl(bay: Option<String>, note: Option<String>) -> ExternResult<()> {
+    let (_, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.customer {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer can check in for pickup".to_string()
+        )));
+    }
+    if !is_pickup_order()? {
+        return Err(wasm_error!(WasmErrorInner::Guest("This order is not a pickup order".to_string())));
+    }
+    let status = current_status()?.unwrap_or_default();
+    if status != "Claimed" && status != "ReadyForPickup" {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot check in for an order in {}", status
+        ))));
+    }
+
+    warn!("🏬 FULFILLMENT: Customer checked in at bay {:?}", bay);
+    notify_agents(
+        RemoteCartSignal::CustomerCheckedIn {
+            bay,
+            note,
+            checked_in_at: sys_time()?.as_micros() as u64,
+        },
+        vec![assignment.shopper],
+    );
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::{
+    current_status, get_public_cart_path, set_delivery_address_impl, update_delivery_address_impl,
+};
+use crate::delivery_proof::get_delivery_address_for_caller;
+use crate::encryption::{find_encrypted_delivery_data, open_delivery_data, seal_delivery_data};
+use crate::order::require_order_assignment;
+use crate::reconciliation::{get_order_reconciliation_impl, LineOutcome};
+
+// One line of the slip packed with the order
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PackingSlipLine {
+    pub product_name: String,
+    pub quantity: f64,
+    pub amount: Option<Money>, // None when prices are hidden
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PackingSlip {
+    pub is_gift: bool,
+    pub recipient_name: Option<String>,
+    pub gift_message: Option<String>,
+    pub hide_prices: bool,
+    pub lines: Vec<PackingSlipLine>,
+    pub total: Option<Money>, // None when prices are hidden
+}
+
+fn require_editable_cart() -> ExternResult<()> {
+    if let Some(status) = current_status()? {
+        if status != "Shopping" {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Gift details can only be changed while shopping (status is {})", status
+            ))));
+        }
+    }
+    Ok(())
+}
+
+// Remove the public path link to the current sealed gift details, if any
+fn unlink_gift_details() -> ExternResult<()> {
+    let (record, _) = match find_encrypted_delivery_data(DeliveryDataKind::Gift)? {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    let public_path = get_public_cart_path()?;
+    let links = get_links(
+        GetLinksInputBuilder::try_new(public_path.path_entry_hash()?, LinkTypes::PublicPathToCartData)?.build()
+    )?;
+    for link in links {
+        if link.target.clone().into_action_hash().as_ref() == Some(record.action_address()) {
+            delete_link(link.create_link_hash)?;
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Flag the session as a gift. The recipient's address becomes the delivery address of this
+// session only - it is never saved to the customer's profile.
+pub(crate) fn set_gift_details_impl(details: GiftDetails, recipient_address: Option<Address>) -> ExternResult<ActionHash> {
+    require_editable_cart()?;
+    if let Some(reason) = validate_gift_details(&details) {
+        return Err(wasm_error!(WasmErrorInner::Guest(reason)));
+    }
+
+    if let Some(address) = recipient_address {
+        if let Some((previous_address_hash, _)) = get_delivery_address_for_caller()? {
+            update_delivery_address_impl(previous_address_hash, address)?;
+        } else {
+            set_delivery_address_impl(address)?;
+        }
+    }
+
+    unlink_gift_details()?;
+    let sealed = seal_delivery_data(DeliveryDataKind::Gift, &details)?;
+    let gift_hash = create_entry(EntryTypes::EncryptedDeliveryData(sealed))?;
+    let public_path = get_public_cart_path()?;
+    create_link(public_path.path_entry_hash()?, gift_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
+
+    warn!("🎁 GIFT: Session flagged as a gift (prices hidden: {})", details.hide_prices);
+    Ok(gift_hash)
+}
+
+// Turn a gift session back into a regular order - the delivery address is left as it is
+pub(crate) fn clear_gift_details_impl() -> ExternResult<()> {
+    require_editable_cart()?;
+    unlink_gift_details()
+}
+
+// The gift details, when this is a gift and the caller can open them
+pub(crate) fn get_gift_details_impl() -> ExternResult<Option<GiftDetails>> {
+    match find_encrypted_delivery_data(DeliveryDataKind::Gift)? {
+        Some((_, sealed)) => open_delivery_data(&sealed),
+        None => Ok(None),
+    }
+}
+
+// What goes in the bag - delivered lines from the reconciliation, with prices left off when the
+// customer published the order with hide_prices
+pub(crate) fn get_packing_slip_impl() -> ExternResult<PackingSlip> {
+    let (_, assignment) = require_order_assignment()?;
+    let me = agent_info()?.agent_initial_pubkey;
+    if me != assignment.shopper && me != assignment.customer {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer and the assigned shopper can see the packing slip".to_string()
+        )));
+    }
+
+    // The published snapshot is authoritative - the gift details may not be shared yet
+    let published = get(assignment.published_status_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Published order status not found".to_string())))?;
+    let published = SessionStatus::try_from(published)?;
+    let gift = get_gift_details_impl()?;
+    let hide_prices = published.hide_prices;
+
+    let reconciliation = get_order_reconciliation_impl()?;
+    let lines = reconciliation
+        .lines
+        .into_iter()
+        .filter(|line| line.outcome != LineOutcome::Missing)
+        .map(|line| PackingSlipLine {
+            product_name: line.substitute.map_or(line.product_name, |substitute| substitute.product_name),
+            quantity: line.delivered_quantity,
+            amount: if hide_prices { None } else { Some(line.delivered_amount) },
+        })
+        .collect();
+
+    Ok(PackingSlip {
+        is_gift: published.is_gift,
+        recipient_name: gift.as_ref().map(|gift| gift.recipient_name.clone()),
+        gift_message: gift.and_then(|gift| gift.gift_message),
+        hide_prices,
+        lines,
+        total: if hide_prices { None } else { Some(reconciliation.delivered_total) },
+    })
+}
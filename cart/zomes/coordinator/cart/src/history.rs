@@ -0,0 +1,261 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::cart::get_public_cart_path;
+
+// What happened at one point in the cart's life
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum CartHistoryChange {
+    ItemAdded {
+        cart_product_hash: ActionHash,
+        product_id: String,
+        product_name: String,
+        quantity: f64,
+    },
+    QuantityChanged {
+        cart_product_hash: ActionHash,
+        product_id: String,
+        product_name: String,
+        from: f64,
+        to: f64,
+    },
+    ItemRemoved {
+        cart_product_hash: ActionHash,
+        product_id: String,
+        product_name: String,
+        previous_quantity: f64,
+    },
+    StatusChanged {
+        from: Option<String>,
+        to: String,
+    },
+    AddressChanged {
+        address_hash: ActionHash,
+    },
+    DeliveryTimeSlotChanged {
+        date: u64,
+        time_slot: String,
+    },
+    DeliveryInstructionsChanged {
+        instructions_hash: ActionHash,
+    },
+    GiftDetailsChanged {
+        gift_hash: ActionHash,
+    },
+}
+
+// A single timeline entry
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CartHistoryEvent {
+    pub timestamp: u64,
+    pub author: AgentPubKey,
+    pub change: CartHistoryChange,
+}
+
+// One CreateLink on the public path with its (possible) deletion
+struct LinkEvent {
+    target_hash: ActionHash,
+    tag: LinkTag,
+    created_at: u64,
+    author: AgentPubKey,
+    prev_action: ActionHash,
+    deleted: Option<LinkDeletion>,
+}
+
+struct LinkDeletion {
+    deleted_at: u64,
+    deleted_by: AgentPubKey,
+    action_hash: ActionHash,
+}
+
+impl LinkEvent {
+    // A quantity change deletes the old link and creates its replacement as the very next action
+    fn replaces(&self, previous: &LinkEvent) -> bool {
+        previous
+            .deleted
+            .as_ref()
+            .is_some_and(|deletion| deletion.action_hash == self.prev_action)
+    }
+}
+
+// Rebuild the cart timeline from every link ever created on (and deleted from) the public path
+pub(crate) fn get_cart_history_impl() -> ExternResult<Vec<CartHistoryEvent>> {
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    let details = get_link_details(
+        public_hash,
+        LinkTypes::PublicPathToCartData,
+        None,
+        GetOptions::default(),
+    )?;
+
+    let mut link_events = Vec::new();
+    for (create, deletes) in details.into_inner() {
+        let create_link = match create.action() {
+            Action::CreateLink(create_link) => create_link.clone(),
+            _ => continue,
+        };
+        let target_hash = match create_link.target_address.into_action_hash() {
+            Some(hash) => hash,
+            None => continue,
+        };
+        let deleted = deletes
+            .iter()
+            .map(|delete| LinkDeletion {
+                deleted_at: delete.action().timestamp().as_micros() as u64,
+                deleted_by: delete.action().author().clone(),
+                action_hash: delete.action_address().clone(),
+            })
+            .min_by_key(|deletion| deletion.deleted_at);
+        link_events.push(LinkEvent {
+            target_hash,
+            tag: create_link.tag,
+            created_at: create_link.timestamp.as_micros() as u64,
+            author: create_link.author,
+            prev_action: create_link.prev_action,
+            deleted,
+        });
+    }
+    link_events.sort_by_key(|event| event.created_at);
+
+    // Fetch each target once
+    let mut records: HashMap<ActionHash, Record> = HashMap::new();
+    for event in &link_events {
+        if !records.contains_key(&event.target_hash) {
+            if let Some(record) = get(event.target_hash.clone(), GetOptions::default())? {
+                records.insert(event.target_hash.clone(), record);
+            }
+        }
+    }
+
+    let mut history = Vec::new();
+    let mut product_links: HashMap<ActionHash, Vec<&LinkEvent>> = HashMap::new();
+    let mut last_status: Option<String> = None;
+
+    for event in &link_events {
+        let record = match records.get(&event.target_hash) {
+            Some(record) => record,
+            None => continue,
+        };
+
+        if CartProduct::try_from(record.clone()).is_ok() {
+            product_links.entry(event.target_hash.clone()).or_default().push(event);
+        } else if let Ok(status) = SessionStatus::try_from(record.clone()) {
+            if last_status.as_deref() != Some(status.status.as_str()) {
+                history.push(CartHistoryEvent {
+                    timestamp: event.created_at,
+                    author: event.author.clone(),
+                    change: CartHistoryChange::StatusChanged {
+                        from: last_status.clone(),
+                        to: status.status.clone(),
+                    },
+                });
+                last_status = Some(status.status);
+            }
+        } else if let Ok(sealed) = EncryptedDeliveryData::try_from(record.clone()) {
+            // Re-sealing for the shopper is an update of the same data, not a change
+            if matches!(record.action(), Action::Update(_)) {
+                continue;
+            }
+            let change = match sealed.kind {
+                DeliveryDataKind::Address => CartHistoryChange::AddressChanged {
+                    address_hash: event.target_hash.clone(),
+                },
+                DeliveryDataKind::Instructions => CartHistoryChange::DeliveryInstructionsChanged {
+                    instructions_hash: event.target_hash.clone(),
+                },
+                DeliveryDataKind::Gift => CartHistoryChange::GiftDetailsChanged {
+                    gift_hash: event.target_hash.clone(),
+                },
+            };
+            history.push(CartHistoryEvent {
+                timestamp: event.created_at,
+                author: event.author.clone(),
+                change,
+            });
+        } else if Address::try_from(record.clone()).is_ok() {
+            history.push(CartHistoryEvent {
+                timestamp: event.created_at,
+                author: event.author.clone(),
+                change: CartHistoryChange::AddressChanged {
+                    address_hash: event.target_hash.clone(),
+                },
+            });
+        } else if let Ok(time_slot) = DeliveryTimeSlot::try_from(record.clone()) {
+            history.push(CartHistoryEvent {
+                timestamp: event.created_at,
+                author: event.author.clone(),
+                change: CartHistoryChange::DeliveryTimeSlotChanged {
+                    date: time_slot.date,
+                    time_slot: time_slot.time_slot,
+                },
+            });
+        } else if DeliveryInstructions::try_from(record.clone()).is_ok() {
+            history.push(CartHistoryEvent {
+                timestamp: event.created_at,
+                author: event.author.clone(),
+                change: CartHistoryChange::DeliveryInstructionsChanged {
+                    instructions_hash: event.target_hash.clone(),
+                },
+            });
+        }
+    }
+
+    // A quantity link replaced in the same change is a quantity change; one deleted without a replacement
+    // is a removal, and a later link for the same entry (re-add, restore, undo) adds it back
+    for (cart_product_hash, events) in product_links {
+        let product = match records.get(&cart_product_hash).map(|record| CartProduct::try_from(record.clone())) {
+            Some(Ok(product)) => product,
+            _ => continue,
+        };
+
+        for (index, event) in events.iter().enumerate() {
+            let (quantity, _) = CartQuantityTag::from_link_tag(&event.tag);
+            let replaced = index
+                .checked_sub(1)
+                .map(|previous| events[previous])
+                .filter(|previous| event.replaces(previous));
+            let change = match replaced {
+                Some(previous) => CartHistoryChange::QuantityChanged {
+                    cart_product_hash: cart_product_hash.clone(),
+                    product_id: product.product_id.clone(),
+                    product_name: product.product_name.clone(),
+                    from: CartQuantityTag::from_link_tag(&previous.tag).0,
+                    to: quantity,
+                },
+                None => CartHistoryChange::ItemAdded {
+                    cart_product_hash: cart_product_hash.clone(),
+                    product_id: product.product_id.clone(),
+                    product_name: product.product_name.clone(),
+                    quantity,
+                },
+            };
+            history.push(CartHistoryEvent {
+                timestamp: event.created_at,
+                author: event.author.clone(),
+                change,
+            });
+
+            let replaced_by_next = events.get(index + 1).is_some_and(|next| next.replaces(event));
+            if let (Some(deletion), false) = (&event.deleted, replaced_by_next) {
+                history.push(CartHistoryEvent {
+                    timestamp: deletion.deleted_at,
+                    author: deletion.deleted_by.clone(),
+                    change: CartHistoryChange::ItemRemoved {
+                        cart_product_hash: cart_product_hash.clone(),
+                        product_id: product.product_id.clone(),
+                        product_name: product.product_name.clone(),
+                        previous_quantity: quantity,
+                    },
+                });
+            }
+        }
+    }
+
+    history.sort_by_key(|event| event.timestamp);
+    Ok(history)
+}
@@ -2,7 +2,44 @@ use cart_integrity::*;
 use hdk::prelude::*;
 use serde::{Deserialize, Serialize};
 
+mod batch;
 mod cart;
+mod chat;
+mod checkout;
+mod delivery_proof;
+mod encryption;
+mod fulfillment;
+mod gift;
+mod history;
+mod location;
+mod money;
+mod notifications;
+mod offline;
+mod order;
+mod payment;
+mod pick_list;
+mod picking;
+mod properties;
+mod rating;
+mod recurring;
+mod reconciliation;
+mod scan;
+mod shopping_list;
+mod signals;
+mod undo;
+
+// Called the first time a zome call is made to the cell - lets peers deliver remote signals to us
+#[hdk_extern]
+pub fn init() -> ExternResult<InitCallbackResult> {
+    signals::grant_remote_signal_capability()?;
+    Ok(InitCallbackResult::Pass)
+}
+
+// Receive a remote signal from the other party of the order and re-emit it to the UI
+#[hdk_extern]
+pub fn recv_remote_signal(signal: signals::RemoteCartSignal) -> ExternResult<()> {
+    signals::handle_remote_signal(signal)
+}
 
 // Input struct for updating delivery address
 #[derive(Serialize, Deserialize, Debug)]
@@ -25,6 +62,184 @@ pub struct RemoveCartItemInput {
     pub quantity: f64,
 }
 
+// Input struct for rating the other party of a delivered order
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RateOrderInput {
+    pub stars: u8,
+    pub tags: Vec<String>,
+    pub comment: Option<String>,
+}
+
+// Input struct for recording what the shopper picked for a cart line
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecordPickInput {
+    pub cart_product_hash: ActionHash,
+    pub picked_quantity: f64,
+    // Must carry the catalog's signed price_quote, as cart lines do
+    #[serde(default)]
+    pub substitute: Option<CartProduct>,
+}
+
+// Input struct for reading session data - local_only skips the network (offline mode)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetSessionDataInput {
+    #[serde(default)]
+    pub local_only: bool,
+}
+
+// Input struct for queueing a cart change while offline - op_id makes queueing and replay idempotent
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QueueCartOpInput {
+    pub op_id: String,
+    pub op: CartOp,
+}
+
+// Input struct for checking a scanned barcode against a cart line
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifyScanInput {
+    pub cart_product_hash: ActionHash,
+    pub scanned_upc: String,
+}
+
+// Input struct for sending a chat message - text may be empty when a quick reply is given
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendOrderMessageInput {
+    pub text: String,
+    pub quick_reply: Option<QuickReply>,
+}
+
+// Input struct for one chunk of the delivery photo
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadPhotoChunkInput {
+    pub chunk_index: u32,
+    pub bytes: Vec<u8>,
+}
+
+// Input struct for creating a standing order - dates are the UI's millisecond timestamps
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateRecurringOrderInput {
+    pub lines: Vec<RecurringOrderLine>,
+    pub cadence: RecurrenceCadence,
+    pub time_slot: String,
+    pub address_hash: ActionHash,
+    pub address: Address,
+    pub first_delivery_date: u64,
+}
+
+// Input struct for adding a list item - quantity is parsed from text ("2 lemons") when omitted
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AddShoppingListItemInput {
+    pub list_hash: ActionHash,
+    pub text: String,
+    pub quantity: Option<f64>,
+    pub product: Option<CartProduct>,
+}
+
+// Input struct for sharing or unsharing a list
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShareShoppingListInput {
+    pub list_hash: ActionHash,
+    pub agent: AgentPubKey,
+    pub shared: bool,
+}
+
+// Input struct for checking a list item off
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CheckShoppingListItemInput {
+    pub item_hash: ActionHash,
+    pub checked: bool,
+}
+
+// Input struct for turning a list into cart lines
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConvertListToCartInput {
+    pub list_hash: ActionHash,
+    // Current catalog snapshots for the list's UPCs / product ids, fetched by the UI
+    #[serde(default)]
+    pub catalog_products: Vec<CartProduct>,
+    pub session_cell_id: Option<CellId>,
+}
+
+// Input struct for materializing recurring orders - null means no fresh catalog snapshots
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MaterializeDueOrdersInput {
+    // Current catalog snapshots (with fresh price quotes) for the templates' products, fetched by the UI
+    #[serde(default)]
+    pub catalog_products: Vec<CartProduct>,
+}
+
+// Input struct for publishing (or dry-run checking) an order - null means no confirmations given
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CheckoutInput {
+    #[serde(default)]
+    pub age_confirmed: bool, // Customer confirms they meet the minimum age for restricted items
+}
+
+// Input struct for choosing delivery or pickup
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetFulfillmentModeInput {
+    pub mode: FulfillmentMode,
+    pub store: Option<StoreLocation>, // Required for Pickup
+}
+
+// Input struct for a customer arriving at the store
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CheckInForPickupInput {
+    pub bay: Option<String>,
+    pub note: Option<String>,
+}
+
+// Input struct for flagging the session as a gift
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetGiftDetailsInput {
+    pub details: GiftDetails,
+    pub recipient_address: Option<Address>, // Becomes this session's delivery address
+}
+
+// Input struct for batching claimed orders into one store trip
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateBatchInput {
+    pub order_dna_hashes: Vec<DnaHash>, // The order sessions' cells (this agent's cell for each DNA hash)
+    pub start: batch::GeoPoint,         // Where the delivery route starts - usually the store
+}
+
+// Input struct for cancelling an order
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CancelOrderInput {
+    pub reason: CancellationReason,
+    pub note: Option<String>,
+}
+
+// Input struct for a live shopper location update
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShareLocationInput {
+    pub lat: f64,
+    pub lng: f64,
+    #[serde(default)]
+    pub persist_trail: bool,
+}
+
+// Input struct for a page of notifications - null reads the newest page
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GetNotificationsInput {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+// Input struct for notification preferences - the muted kinds replace the previous list
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetNotificationPreferencesInput {
+    pub muted: Vec<NotificationKind>,
+}
+
+// Input struct for estimating arrival - both fields optional
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetOrderEtaInput {
+    pub shopper_position: Option<location::ShopperPosition>,
+    pub average_speed_kmh: Option<f64>,
+}
+
 // OPTIMIZED: Add cart item with quantity (new recommended function)
 #[hdk_extern]
 pub fn add_cart_item(input: AddCartItemInput) -> ExternResult<ActionHash> {
@@ -37,6 +252,18 @@ pub fn remove_cart_item(input: RemoveCartItemInput) -> ExternResult<ActionHash>
     cart::remove_item_impl(input.product_id, input.quantity)
 }
 
+// Revert the most recent add, quantity change or removal of a cart line
+#[hdk_extern]
+pub fn undo_last_cart_change(_: ()) -> ExternResult<Option<undo::CartLineChange>> {
+    undo::undo_last_cart_change_impl()
+}
+
+// Bring a removed product back with its previous quantity and note
+#[hdk_extern]
+pub fn restore_removed_item(product_id: String) -> ExternResult<undo::CartLineChange> {
+    undo::restore_removed_item_impl(product_id)
+}
+
 
 // Get all current cart items using query()
 #[hdk_extern]
@@ -50,10 +277,16 @@ pub fn get_session_status(_: ()) -> ExternResult<Option<Record>> {
     cart::get_session_status_impl()
 }
 
-// Update session status to "AwaitingShopper"
+// Update session status to "Checkout" - requires a valid checkout and an authorized payment
+#[hdk_extern]
+pub fn publish_order(input: Option<CheckoutInput>) -> ExternResult<ActionHash> {
+    cart::publish_order_impl(input.unwrap_or_default().age_confirmed)
+}
+
+// Dry run of publish_order's checks - reports every blocking problem without publishing
 #[hdk_extern]
-pub fn publish_order(_: ()) -> ExternResult<ActionHash> {
-    cart::publish_order_impl()
+pub fn validate_checkout(input: Option<CheckoutInput>) -> ExternResult<checkout::CheckoutValidation> {
+    checkout::validate_checkout_impl(input.unwrap_or_default().age_confirmed)
 }
 
 // Update session status back to "Building"
@@ -86,10 +319,395 @@ pub fn set_delivery_instructions(instructions: DeliveryInstructions) -> ExternRe
     cart::set_delivery_instructions_impl(instructions)
 }
 
-// Get all session data in one call
+// Get all session data in one call - pass { local_only: true } to read without the network
+#[hdk_extern]
+pub fn get_session_data(input: Option<GetSessionDataInput>) -> ExternResult<cart::CartSessionData> {
+    cart::get_session_data_impl(input.unwrap_or_default().local_only)
+}
+
+// Queue a cart change on the source chain while offline
+#[hdk_extern]
+pub fn queue_cart_op(input: QueueCartOpInput) -> ExternResult<ActionHash> {
+    offline::queue_cart_op_impl(input.op_id, input.op)
+}
+
+// Cart changes queued offline that have not been replayed yet
 #[hdk_extern]
-pub fn get_session_data(_: ()) -> ExternResult<cart::CartSessionData> {
-    cart::get_session_data_impl()
+pub fn get_pending_cart_ops(_: ()) -> ExternResult<Vec<offline::QueuedCartOpWithHash>> {
+    offline::get_pending_cart_ops_impl()
 }
 
+// Replay queued cart changes against the live cart once back online, reporting conflicts
+#[hdk_extern]
+pub fn replay_cart_ops(_: ()) -> ExternResult<offline::CartReplayReport> {
+    offline::replay_cart_ops_impl()
+}
 
+// Cancel the order - customer before the slot cut-off, assigned shopper with a reason
+#[hdk_extern]
+pub fn cancel_order(input: CancelOrderInput) -> ExternResult<ActionHash> {
+    order::cancel_order_impl(input.reason, input.note)
+}
+
+// Shopper claims a published order
+#[hdk_extern]
+pub fn claim_order(_: ()) -> ExternResult<ActionHash> {
+    order::claim_order_impl()
+}
+
+// Get the shopper assignment for this order
+#[hdk_extern]
+pub fn get_order_assignment(_: ()) -> ExternResult<Option<Record>> {
+    Ok(order::get_order_assignment_impl()?.map(|(record, _)| record))
+}
+
+// Assigned shopper marks the order delivered, submitting the proof of delivery
+#[hdk_extern]
+pub fn mark_order_delivered(proof: delivery_proof::DeliveryProofInput) -> ExternResult<ActionHash> {
+    order::mark_order_delivered_impl(proof)
+}
+
+// Choose delivery or store pickup - the delivery time slot doubles as the pickup slot
+#[hdk_extern]
+pub fn set_fulfillment_mode(input: SetFulfillmentModeInput) -> ExternResult<ActionHash> {
+    fulfillment::set_fulfillment_mode_impl(input.mode, input.store)
+}
+
+// Get the session's fulfillment choice (None means delivery)
+#[hdk_extern]
+pub fn get_order_fulfillment(_: ()) -> ExternResult<Option<OrderFulfillment>> {
+    Ok(fulfillment::get_order_fulfillment_impl()?.map(|(_, fulfillment)| fulfillment))
+}
+
+// Assigned shopper tells the customer a pickup order is ready
+#[hdk_extern]
+pub fn mark_ready_for_pickup(_: ()) -> ExternResult<ActionHash> {
+    fulfillment::mark_ready_for_pickup_impl()
+}
+
+// Customer tells the shopper they have arrived for pickup
+#[hdk_extern]
+pub fn check_in_for_pickup(input: CheckInForPickupInput) -> ExternResult<()> {
+    fulfillment::check_in_for_pickup_impl(input.bay, input.note)
+}
+
+// Flag the session as a gift - recipient, message and hide-prices are sealed like the address
+#[hdk_extern]
+pub fn set_gift_details(input: SetGiftDetailsInput) -> ExternResult<ActionHash> {
+    gift::set_gift_details_impl(input.details, input.recipient_address)
+}
+
+// Turn a gift session back into a regular order
+#[hdk_extern]
+pub fn clear_gift_details(_: ()) -> ExternResult<()> {
+    gift::clear_gift_details_impl()
+}
+
+// Gift details for the caller (None if not a gift or not shared with the caller yet)
+#[hdk_extern]
+pub fn get_gift_details(_: ()) -> ExternResult<Option<GiftDetails>> {
+    gift::get_gift_details_impl()
+}
+
+// Packing slip for the claimed order - prices are left off when the gift hides them
+#[hdk_extern]
+pub fn get_packing_slip(_: ()) -> ExternResult<gift::PackingSlip> {
+    gift::get_packing_slip_impl()
+}
+
+// Shopper combines several claimed orders into one trip - merged pick list and delivery stop order
+#[hdk_extern]
+pub fn create_batch(input: CreateBatchInput) -> ExternResult<batch::BatchPlan> {
+    batch::create_batch_impl(input.order_dna_hashes, input.start)
+}
+
+// Called by the shopper's batch planner in each order cell
+#[hdk_extern]
+pub fn get_batch_order_summary(_: ()) -> ExternResult<batch::BatchOrderSummary> {
+    batch::get_batch_order_summary_impl()
+}
+
+// The shopper's batches, newest first
+#[hdk_extern]
+pub fn get_shopper_batches(_: ()) -> ExternResult<Vec<(ActionHash, ShopperBatch)>> {
+    batch::get_shopper_batches_impl()
+}
+
+// The order's lines in aisle walking order, grouped by temperature zone (frozen last)
+#[hdk_extern]
+pub fn get_pick_list(layout: Option<pick_list::StoreLayout>) -> ExternResult<pick_list::PickList> {
+    pick_list::get_pick_list_impl(layout)
+}
+
+// Upload one chunk of the delivery photo (referenced by the proof)
+#[hdk_extern]
+pub fn upload_delivery_photo_chunk(input: UploadPhotoChunkInput) -> ExternResult<ActionHash> {
+    delivery_proof::upload_delivery_photo_chunk_impl(input.chunk_index, input.bytes)
+}
+
+// Get the delivery proof for this order
+#[hdk_extern]
+pub fn get_delivery_proof(_: ()) -> ExternResult<Option<Record>> {
+    delivery_proof::get_delivery_proof_impl()
+}
+
+// Reassemble the chunked delivery photo
+#[hdk_extern]
+pub fn get_delivery_photo(_: ()) -> ExternResult<Option<Vec<u8>>> {
+    delivery_proof::get_delivery_photo_impl()
+}
+
+// Rate the other party of a delivered order
+#[hdk_extern]
+pub fn rate_order(input: RateOrderInput) -> ExternResult<ActionHash> {
+    rating::rate_order_impl(input.stars, input.tags, input.comment)
+}
+
+// Get all ratings an agent has received
+#[hdk_extern]
+pub fn get_ratings_for_agent(agent: AgentPubKey) -> ExternResult<Vec<(ActionHash, Rating)>> {
+    rating::get_ratings_for_agent_impl(agent)
+}
+
+// Get aggregate rating figures for an agent
+#[hdk_extern]
+pub fn get_agent_rating_summary(agent: AgentPubKey) -> ExternResult<rating::AgentRatingSummary> {
+    rating::get_agent_rating_summary_impl(agent)
+}
+
+// Assigned shopper records the picked quantity or weight for a cart line
+#[hdk_extern]
+pub fn record_pick(input: RecordPickInput) -> ExternResult<ActionHash> {
+    picking::record_pick_impl(input.cart_product_hash, input.picked_quantity, input.substitute)
+}
+
+// Check a scanned barcode against a cart line in this order's cell - matches are recorded as picks
+#[hdk_extern]
+pub fn verify_scan(input: VerifyScanInput) -> ExternResult<scan::ScanVerification> {
+    scan::verify_scan_impl(input.cart_product_hash, input.scanned_upc)
+}
+
+// Get all cart lines with their latest pick records
+#[hdk_extern]
+pub fn get_picked_lines(_: ()) -> ExternResult<Vec<picking::PickedCartLine>> {
+    picking::get_picked_lines_impl()
+}
+
+// Compare what was ordered with what was picked, line by line, with the refund due
+#[hdk_extern]
+pub fn get_order_reconciliation(_: ()) -> ExternResult<reconciliation::OrderReconciliation> {
+    reconciliation::get_order_reconciliation_impl()
+}
+
+// Create a payment intent for the current cart (provider defaults to "mock")
+#[hdk_extern]
+pub fn create_payment_intent(provider: Option<String>) -> ExternResult<ActionHash> {
+    payment::create_payment_intent_impl(provider)
+}
+
+// Authorize the current payment intent - required before publish_order
+#[hdk_extern]
+pub fn authorize_payment(_: ()) -> ExternResult<ActionHash> {
+    payment::authorize_payment_impl()
+}
+
+// Capture the final fulfillment total after delivery
+#[hdk_extern]
+pub fn capture_payment(_: ()) -> ExternResult<ActionHash> {
+    payment::capture_payment_impl()
+}
+
+// Refund a captured payment (full refund when no amount is given) or release an authorization
+#[hdk_extern]
+pub fn refund_payment(amount: Option<Money>) -> ExternResult<ActionHash> {
+    payment::refund_payment_impl(amount)
+}
+
+// Get the current payment intent
+#[hdk_extern]
+pub fn get_payment_intent(_: ()) -> ExternResult<Option<Record>> {
+    Ok(payment::get_payment_intent_impl()?.map(|(record, _)| record))
+}
+
+// Customer re-seals address and instructions for the assigned shopper once the order is claimed
+#[hdk_extern]
+pub fn share_delivery_details(_: ()) -> ExternResult<Vec<ActionHash>> {
+    encryption::share_delivery_details_impl()
+}
+
+// Send a chat message (or quick reply) to the other party of the order
+#[hdk_extern]
+pub fn send_order_message(input: SendOrderMessageInput) -> ExternResult<ActionHash> {
+    chat::send_order_message_impl(input.text, input.quick_reply)
+}
+
+// Get the order chat thread, oldest first
+#[hdk_extern]
+pub fn get_order_messages(_: ()) -> ExternResult<Vec<chat::OrderChatMessage>> {
+    chat::get_order_messages_impl()
+}
+
+// Mark all messages from the other party as read
+#[hdk_extern]
+pub fn mark_messages_read(_: ()) -> ExternResult<Vec<ActionHash>> {
+    chat::mark_messages_read_impl()
+}
+
+// Get a page of this order's notifications, newest first, with the unread count
+#[hdk_extern]
+pub fn get_notifications(input: Option<GetNotificationsInput>) -> ExternResult<notifications::NotificationPage> {
+    let input = input.unwrap_or_default();
+    notifications::get_notifications_impl(input.offset, input.limit)
+}
+
+// Mark notifications as read - returns the ones that were not read yet
+#[hdk_extern]
+pub fn mark_read(notification_hashes: Vec<ActionHash>) -> ExternResult<Vec<ActionHash>> {
+    notifications::mark_read_impl(notification_hashes)
+}
+
+// Mute notification kinds - muted kinds are neither stored nor signalled
+#[hdk_extern]
+pub fn set_notification_preferences(input: SetNotificationPreferencesInput) -> ExternResult<ActionHash> {
+    notifications::set_muted_notification_kinds_impl(input.muted)
+}
+
+// Get the currently muted notification kinds
+#[hdk_extern]
+pub fn get_notification_preferences(_: ()) -> ExternResult<Vec<NotificationKind>> {
+    notifications::get_muted_notification_kinds_impl()
+}
+
+// Shopper tells the customer they have left the store with the order
+#[hdk_extern]
+pub fn notify_en_route(_: ()) -> ExternResult<()> {
+    notifications::notify_en_route_impl()
+}
+
+// Customer app polls this to be reminded once when the slot's cut-off is near
+#[hdk_extern]
+pub fn check_slot_cutoff(_: ()) -> ExternResult<Option<ActionHash>> {
+    notifications::check_slot_cutoff_impl()
+}
+
+// Shopper pushes a live location to the customer (optionally persisting a coarse trail point)
+#[hdk_extern]
+pub fn share_shopper_location(input: ShareLocationInput) -> ExternResult<Option<ActionHash>> {
+    location::share_shopper_location_impl(input.lat, input.lng, input.persist_trail)
+}
+
+// Estimate the shopper's arrival at the delivery address
+#[hdk_extern]
+pub fn get_order_eta(input: GetOrderEtaInput) -> ExternResult<Option<location::OrderEta>> {
+    location::get_order_eta_impl(input.shopper_position, input.average_speed_kmh)
+}
+
+// Get the persisted coarse location trail for dispute resolution
+#[hdk_extern]
+pub fn get_location_trail(_: ()) -> ExternResult<Vec<LocationTrailPoint>> {
+    location::get_location_trail_impl()
+}
+
+// Rebuild the cart timeline (adds, quantity changes, removals, status/address/slot changes) for support and disputes
+#[hdk_extern]
+pub fn get_cart_history(_: ()) -> ExternResult<Vec<history::CartHistoryEvent>> {
+    history::get_cart_history_impl()
+}
+
+// Create a standing weekly or biweekly order template
+#[hdk_extern]
+pub fn create_recurring_order(input: CreateRecurringOrderInput) -> ExternResult<ActionHash> {
+    recurring::create_recurring_order_impl(
+        input.lines,
+        input.cadence,
+        input.time_slot,
+        input.address_hash,
+        input.address,
+        input.first_delivery_date,
+    )
+}
+
+// Get this customer's recurring order templates
+#[hdk_extern]
+pub fn get_recurring_orders(_: ()) -> ExternResult<Vec<recurring::RecurringOrderWithHash>> {
+    recurring::get_recurring_orders_impl()
+}
+
+// Create cart sessions for every template that is due (re-priced from the given catalog snapshots)
+// and notify the customer to confirm them
+#[hdk_extern]
+pub fn materialize_due_orders(input: Option<MaterializeDueOrdersInput>) -> ExternResult<Vec<recurring::MaterializedOrder>> {
+    recurring::materialize_due_orders_impl(input.unwrap_or_default().catalog_products)
+}
+
+// Stop materializing a template until it is resumed
+#[hdk_extern]
+pub fn pause_recurring_order(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    recurring::pause_recurring_order_impl(recurring_order_hash)
+}
+
+// Resume a paused template from its next future date
+#[hdk_extern]
+pub fn resume_recurring_order(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    recurring::resume_recurring_order_impl(recurring_order_hash)
+}
+
+// Skip only the next occurrence of a template
+#[hdk_extern]
+pub fn skip_next_recurring_order(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    recurring::skip_next_recurring_order_impl(recurring_order_hash)
+}
+
+// Cancel a template for good
+#[hdk_extern]
+pub fn cancel_recurring_order(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    recurring::cancel_recurring_order_impl(recurring_order_hash)
+}
+
+// Create a shopping list owned by the caller
+#[hdk_extern]
+pub fn create_shopping_list(name: String) -> ExternResult<ActionHash> {
+    shopping_list::create_shopping_list_impl(name)
+}
+
+// Get the lists the caller owns or that were shared with them
+#[hdk_extern]
+pub fn get_my_shopping_lists(_: ()) -> ExternResult<Vec<shopping_list::ShoppingListWithHash>> {
+    shopping_list::get_my_shopping_lists_impl()
+}
+
+// Owner shares a list with another agent (or stops sharing it)
+#[hdk_extern]
+pub fn share_shopping_list(input: ShareShoppingListInput) -> ExternResult<ActionHash> {
+    shopping_list::set_shopping_list_member_impl(input.list_hash, input.agent, input.shared)
+}
+
+// Add a free-text or catalog-linked item to a list
+#[hdk_extern]
+pub fn add_shopping_list_item(input: AddShoppingListItemInput) -> ExternResult<ActionHash> {
+    shopping_list::add_shopping_list_item_impl(input.list_hash, input.text, input.quantity, input.product)
+}
+
+// Get all items of a list
+#[hdk_extern]
+pub fn get_shopping_list_items(list_hash: ActionHash) -> ExternResult<Vec<shopping_list::ShoppingListItemWithHash>> {
+    shopping_list::get_shopping_list_items_impl(list_hash)
+}
+
+// Check a list item off or back on
+#[hdk_extern]
+pub fn check_shopping_list_item(input: CheckShoppingListItemInput) -> ExternResult<ActionHash> {
+    shopping_list::set_shopping_list_item_checked_impl(input.item_hash, input.checked)
+}
+
+// Remove an item from its list
+#[hdk_extern]
+pub fn remove_shopping_list_item(item_hash: ActionHash) -> ExternResult<()> {
+    shopping_list::remove_shopping_list_item_impl(item_hash)
+}
+
+// Resolve list items to catalog products and add them to the cart
+#[hdk_extern]
+pub fn convert_list_to_cart(input: ConvertListToCartInput) -> ExternResult<shopping_list::ConvertListResult> {
+    shopping_list::convert_list_to_cart_impl(input.list_hash, input.catalog_products, input.session_cell_id)
+}
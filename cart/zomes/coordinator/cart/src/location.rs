@@ -0,0 +1,152 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::delivery_proof::get_delivery_address_for_caller;
+use crate::order::require_order_assignment;
+use crate::properties::{get_cart_properties, DEFAULT_AVERAGE_SPEED_KMH};
+use crate::signals::{notify_agents, RemoteCartSignal};
+
+// Roads are rarely straight - scale the great-circle distance by this factor
+pub const ROAD_DISTANCE_FACTOR: f64 = 1.3;
+
+// Minimum time between persisted trail points (2 minutes)
+pub const TRAIL_MIN_INTERVAL_MICROS: u64 = 2 * 60 * 1_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShopperPosition {
+    pub lat: f64,
+    pub lng: f64,
+    pub recorded_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderEta {
+    pub from: ShopperPosition,
+    pub distance_meters: f64,
+    pub average_speed_kmh: f64,
+    pub estimated_seconds: u64,
+    pub estimated_arrival: u64, // Microseconds since epoch
+}
+
+// Shopper pushes a live position to the customer; optionally keeps a coarse trail point
+pub(crate) fn share_shopper_location_impl(lat: f64, lng: f64, persist_trail: bool) -> ExternResult<Option<ActionHash>> {
+    let (assignment_hash, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can share their location".to_string()
+        )));
+    }
+    if !is_valid_coordinate(lat, lng) {
+        return Err(wasm_error!(WasmErrorInner::Guest("Coordinates are out of range".to_string())));
+    }
+
+    let recorded_at = sys_time()?.as_micros() as u64;
+
+    // Live positions are ephemeral - they only travel as a remote signal
+    notify_agents(
+        RemoteCartSignal::ShopperLocation {
+            position: ShopperPosition { lat, lng, recorded_at },
+        },
+        vec![assignment.customer],
+    );
+
+    if !persist_trail {
+        return Ok(None);
+    }
+
+    // Keep the trail coarse in time as well as in space
+    if let Some(last) = get_location_trail_for_assignment(&assignment_hash)?.last() {
+        if recorded_at.saturating_sub(last.recorded_at) < TRAIL_MIN_INTERVAL_MICROS {
+            return Ok(None);
+        }
+    }
+
+    let point = LocationTrailPoint {
+        assignment_hash: assignment_hash.clone(),
+        lat: coarsen_coordinate(lat),
+        lng: coarsen_coordinate(lng),
+        recorded_at,
+    };
+    let point_hash = create_entry(EntryTypes::LocationTrailPoint(point))?;
+    create_link(assignment_hash, point_hash.clone(), LinkTypes::AssignmentToTrailPoint, ())?;
+
+    Ok(Some(point_hash))
+}
+
+// Only points of this order recorded by its shopper are read
+fn get_location_trail_for_assignment(assignment_hash: &ActionHash) -> ExternResult<Vec<LocationTrailPoint>> {
+    let assignment = match get(assignment_hash.clone(), GetOptions::default())? {
+        Some(record) => OrderAssignment::try_from(record)?,
+        None => return Ok(Vec::new()),
+    };
+    let links = get_links(
+        GetLinksInputBuilder::try_new(assignment_hash.clone(), LinkTypes::AssignmentToTrailPoint)?.build()
+    )?;
+
+    let mut points = Vec::new();
+    for link in links {
+        if let Some(target_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(target_hash, GetOptions::default())? {
+                if *record.action().author() != assignment.shopper {
+                    continue;
+                }
+                if let Ok(point) = LocationTrailPoint::try_from(record) {
+                    if point.assignment_hash == *assignment_hash {
+                        points.push(point);
+                    }
+                }
+            }
+        }
+    }
+
+    points.sort_by_key(|point| point.recorded_at);
+    Ok(points)
+}
+
+// Get the persisted coarse trail for this order, oldest first
+pub(crate) fn get_location_trail_impl() -> ExternResult<Vec<LocationTrailPoint>> {
+    let (assignment_hash, _) = require_order_assignment()?;
+    get_location_trail_for_assignment(&assignment_hash)
+}
+
+// Estimate arrival from the shopper's last known position to the delivery address
+// The UI passes the latest live position it received; otherwise the last trail point is used.
+pub(crate) fn get_order_eta_impl(
+    shopper_position: Option<ShopperPosition>,
+    average_speed_kmh: Option<f64>,
+) -> ExternResult<Option<OrderEta>> {
+    let from = match shopper_position {
+        Some(position) => position,
+        None => match get_location_trail_impl()?.pop() {
+            Some(point) => ShopperPosition {
+                lat: point.lat,
+                lng: point.lng,
+                recorded_at: point.recorded_at,
+            },
+            None => return Ok(None),
+        },
+    };
+
+    let address = match get_delivery_address_for_caller()? {
+        Some((_, Some(address))) => address,
+        _ => return Ok(None),
+    };
+
+    let average_speed_kmh = average_speed_kmh
+        .or(get_cart_properties()?.average_speed_kmh)
+        .filter(|speed| *speed > 0.0)
+        .unwrap_or(DEFAULT_AVERAGE_SPEED_KMH);
+
+    let distance_meters = distance_meters(from.lat, from.lng, address.lat, address.lng) * ROAD_DISTANCE_FACTOR;
+    let estimated_seconds = (distance_meters / (average_speed_kmh * 1000.0 / 3600.0)).round() as u64;
+    let estimated_arrival = from.recorded_at + estimated_seconds * 1_000_000;
+
+    Ok(Some(OrderEta {
+        from,
+        distance_meters,
+        average_speed_kmh,
+        estimated_seconds,
+        estimated_arrival,
+    }))
+}
@@ -0,0 +1,29 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::cart::CartProductWithHash;
+
+// Currency of the cart - every line snapshot must share it
+pub(crate) fn cart_currency(items: &[CartProductWithHash]) -> String {
+    items
+        .first()
+        .map(|item| item.product.price_at_checkout.currency.clone())
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string())
+}
+
+// Sum amounts that must all be in `currency`
+pub(crate) fn sum_money(amounts: impl IntoIterator<Item = Money>, currency: &str) -> ExternResult<Money> {
+    amounts.into_iter().try_fold(Money::zero(currency), |total, amount| {
+        total.checked_add(&amount).ok_or(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot add an amount in {} to a {} total", amount.currency, currency
+        ))))
+    })
+}
+
+// a - b, clamped at zero
+pub(crate) fn difference_or_zero(a: &Money, b: &Money) -> ExternResult<Money> {
+    let difference = a.checked_sub(b).ok_or(wasm_error!(WasmErrorInner::Guest(format!(
+        "Cannot subtract an amount in {} from one in {}", b.currency, a.currency
+    ))))?;
+    Ok(Money::new(difference.amount_minor.max(0), &difference.currency))
+}
@@ -0,0 +1,203 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::{current_status, find_public_record};
+use crate::fulfillment::is_pickup_order;
+use crate::offline::query_private;
+use crate::order::require_order_assignment;
+use crate::properties::get_cart_properties;
+use crate::signals::{notify_agents, CartSignal, RemoteCartSignal};
+
+pub const DEFAULT_NOTIFICATION_PAGE_SIZE: usize = 20;
+// How long before the slot cut-off the customer is reminded
+pub const SLOT_CUTOFF_WARNING_MILLIS: u64 = 2 * 60 * 60 * 1000;
+
+// What the sender knows about a notification - the recipient adds who sent it and when it arrived
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotificationDraft {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub dedupe_key: String,
+    pub subject_hash: Option<ActionHash>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NotificationWithState {
+    pub notification_hash: ActionHash,
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub read: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NotificationPage {
+    pub notifications: Vec<NotificationWithState>, // Newest first
+    pub total: usize,
+    pub unread_count: usize,
+    pub has_more: bool,
+}
+
+pub(crate) fn get_muted_notification_kinds_impl() -> ExternResult<Vec<NotificationKind>> {
+    // Chain order - the last preferences entry is the current one
+    Ok(query_private::<NotificationPreferences>(UnitEntryTypes::NotificationPreferences)?
+        .pop()
+        .map_or_else(Vec::new, |(_, preferences)| preferences.muted))
+}
+
+pub(crate) fn set_muted_notification_kinds_impl(muted: Vec<NotificationKind>) -> ExternResult<ActionHash> {
+    warn!("🔔 NOTIFICATIONS: Muting {:?}", muted);
+    create_entry(EntryTypes::NotificationPreferences(NotificationPreferences {
+        muted,
+        updated_at: sys_time()?.as_micros() as u64,
+    }))
+}
+
+// Store a notification on this agent's chain and tell the UI - skipped when muted or already stored
+pub(crate) fn record_notification(draft: NotificationDraft, from: Option<AgentPubKey>) -> ExternResult<Option<ActionHash>> {
+    if get_muted_notification_kinds_impl()?.contains(&draft.kind) {
+        return Ok(None);
+    }
+    let existing = query_private::<Notification>(UnitEntryTypes::Notification)?;
+    if existing.iter().any(|(_, notification)| notification.dedupe_key == draft.dedupe_key) {
+        return Ok(None);
+    }
+
+    let notification = Notification {
+        kind: draft.kind,
+        title: draft.title,
+        body: draft.body,
+        dedupe_key: draft.dedupe_key,
+        subject_hash: draft.subject_hash,
+        from,
+        created_at: sys_time()?.as_micros() as u64,
+    };
+    warn!("🔔 NOTIFICATIONS: {:?} - {}", notification.kind, notification.title);
+    let notification_hash = create_entry(EntryTypes::Notification(notification.clone()))?;
+    emit_signal(CartSignal::Notification {
+        notification_hash: notification_hash.clone(),
+        notification,
+    })?;
+    Ok(Some(notification_hash))
+}
+
+// Deliver a notification to the other party of the order - they store it when it arrives
+pub(crate) fn send_notification(draft: NotificationDraft, to: AgentPubKey) {
+    notify_agents(RemoteCartSignal::Notification { notification: draft }, vec![to]);
+}
+
+// A page of this cell's notifications, newest first, with the unread count over all of them
+pub(crate) fn get_notifications_impl(offset: usize, limit: Option<usize>) -> ExternResult<NotificationPage> {
+    let read: Vec<ActionHash> = query_private::<NotificationRead>(UnitEntryTypes::NotificationRead)?
+        .into_iter()
+        .map(|(_, read)| read.notification_hash)
+        .collect();
+    let mut notifications: Vec<NotificationWithState> = query_private::<Notification>(UnitEntryTypes::Notification)?
+        .into_iter()
+        .map(|(notification_hash, notification)| NotificationWithState {
+            read: read.contains(&notification_hash),
+            notification_hash,
+            notification,
+        })
+        .collect();
+    notifications.reverse();
+
+    let total = notifications.len();
+    let unread_count = notifications.iter().filter(|notification| !notification.read).count();
+    let limit = limit.unwrap_or(DEFAULT_NOTIFICATION_PAGE_SIZE);
+    let page = notifications.into_iter().skip(offset).take(limit).collect();
+    Ok(NotificationPage {
+        notifications: page,
+        total,
+        unread_count,
+        has_more: offset.saturating_add(limit) < total,
+    })
+}
+
+// Mark notifications read - already read ones are left alone
+pub(crate) fn mark_read_impl(notification_hashes: Vec<ActionHash>) -> ExternResult<Vec<ActionHash>> {
+    let already_read: Vec<ActionHash> = query_private::<NotificationRead>(UnitEntryTypes::NotificationRead)?
+        .into_iter()
+        .map(|(_, read)| read.notification_hash)
+        .collect();
+    let read_at = sys_time()?.as_micros() as u64;
+
+    let mut marked = Vec::new();
+    for notification_hash in notification_hashes {
+        if already_read.contains(&notification_hash) || marked.contains(&notification_hash) {
+            continue;
+        }
+        create_entry(EntryTypes::NotificationRead(NotificationRead {
+            notification_hash: notification_hash.clone(),
+            read_at,
+        }))?;
+        marked.push(notification_hash);
+    }
+    Ok(marked)
+}
+
+// Shopper has left the store with a delivery order
+pub(crate) fn notify_en_route_impl() -> ExternResult<()> {
+    let (assignment_hash, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can say they are on the way".to_string()
+        )));
+    }
+    if is_pickup_order()? || current_status()?.as_deref() != Some("Claimed") {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only claimed delivery orders can be on the way".to_string()
+        )));
+    }
+
+    send_notification(
+        NotificationDraft {
+            kind: NotificationKind::ShopperEnRoute,
+            title: "Your shopper is on the way".to_string(),
+            body: "Your order has left the store".to_string(),
+            dedupe_key: format!("en_route:{}", assignment_hash),
+            subject_hash: Some(assignment_hash),
+        },
+        assignment.customer,
+    );
+    Ok(())
+}
+
+// Remind the customer when the slot's cut-off is close and the order can still be changed.
+// Nothing runs on a timer, so the customer app calls this periodically - it only notifies once per slot.
+pub(crate) fn check_slot_cutoff_impl() -> ExternResult<Option<ActionHash>> {
+    let status = current_status()?.unwrap_or_else(|| "Shopping".to_string());
+    if status != "Shopping" && status != "Checkout" {
+        return Ok(None);
+    }
+    let (record, time_slot) = match find_public_record::<DeliveryTimeSlot>()? {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+    if *record.action().author() != agent_info()?.agent_initial_pubkey {
+        return Ok(None);
+    }
+
+    let cutoff_hours = get_cart_properties()?.order_cutoff_hours.unwrap_or(DEFAULT_ORDER_CUTOFF_HOURS);
+    let cutoff = time_slot.date.saturating_sub(cutoff_hours * 60 * 60 * 1000);
+    let now = sys_time()?.as_millis() as u64;
+    if now >= cutoff || now + SLOT_CUTOFF_WARNING_MILLIS < cutoff {
+        return Ok(None);
+    }
+
+    let minutes_left = (cutoff - now) / (60 * 1000);
+    record_notification(
+        NotificationDraft {
+            kind: NotificationKind::SlotCutoffApproaching,
+            title: "Your delivery slot closes soon".to_string(),
+            body: format!(
+                "Changes to your {} order close in {} minutes",
+                time_slot.time_slot, minutes_left
+            ),
+            dedupe_key: format!("slot_cutoff:{}", record.action_address()),
+            subject_hash: Some(record.action_address().clone()),
+        },
+        None,
+    )
+}
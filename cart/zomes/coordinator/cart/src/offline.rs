@@ -0,0 +1,147 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::{add_item_impl, current_status, get_current_items_impl, remove_item_impl};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedCartOpWithHash {
+    pub queued_op_hash: ActionHash,
+    #[serde(flatten)]
+    pub queued_op: QueuedCartOp,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CartOpConflictReport {
+    pub op_id: String,
+    pub conflict: CartOpConflict,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CartReplayReport {
+    pub applied: Vec<String>,
+    pub conflicts: Vec<CartOpConflictReport>,
+}
+
+// Queued ops and receipts both live on this agent's source chain - no network needed to read them
+pub(crate) fn query_private<T: TryFrom<Record>>(entry_type: UnitEntryTypes) -> ExternResult<Vec<(ActionHash, T)>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(entry_type.try_into()?)
+            .include_entries(true),
+    )?;
+    Ok(records
+        .into_iter()
+        .filter_map(|record| {
+            let hash = record.action_address().clone();
+            T::try_from(record).ok().map(|entry| (hash, entry))
+        })
+        .collect())
+}
+
+fn replayed_op_ids() -> ExternResult<Vec<String>> {
+    Ok(query_private::<CartOpReceipt>(UnitEntryTypes::CartOpReceipt)?
+        .into_iter()
+        .map(|(_, receipt)| receipt.op_id)
+        .collect())
+}
+
+// Ops waiting for the network, oldest first
+pub(crate) fn get_pending_cart_ops_impl() -> ExternResult<Vec<QueuedCartOpWithHash>> {
+    let replayed = replayed_op_ids()?;
+    Ok(query_private::<QueuedCartOp>(UnitEntryTypes::QueuedCartOp)?
+        .into_iter()
+        .filter(|(_, queued_op)| !replayed.contains(&queued_op.op_id))
+        .map(|(queued_op_hash, queued_op)| QueuedCartOpWithHash { queued_op_hash, queued_op })
+        .collect())
+}
+
+// Record a cart change while offline. Queueing an op_id that is already queued returns the first copy.
+pub(crate) fn queue_cart_op_impl(op_id: String, op: CartOp) -> ExternResult<ActionHash> {
+    let existing = query_private::<QueuedCartOp>(UnitEntryTypes::QueuedCartOp)?
+        .into_iter()
+        .find(|(_, queued_op)| queued_op.op_id == op_id);
+    if let Some((queued_op_hash, _)) = existing {
+        return Ok(queued_op_hash);
+    }
+
+    warn!("📴 OFFLINE QUEUE: Queueing cart op {}", op_id);
+    create_entry(EntryTypes::QueuedCartOp(QueuedCartOp {
+        op_id,
+        op,
+        queued_at: sys_time()?.as_micros() as u64,
+    }))
+}
+
+// Business errors (limits, quotes) become conflicts; anything else - like the network still being
+// down - aborts the replay so the op stays queued
+fn as_conflict<T>(result: ExternResult<T>) -> ExternResult<Result<T, CartOpConflict>> {
+    match result {
+        Ok(value) => Ok(Ok(value)),
+        Err(WasmError { error: WasmErrorInner::Guest(reason), .. }) => {
+            Ok(Err(CartOpConflict::Rejected { reason }))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// Apply one op to the live cart: (cart line touched, conflict)
+fn apply_cart_op(op: CartOp) -> ExternResult<(Option<ActionHash>, Option<CartOpConflict>)> {
+    match op {
+        CartOp::AddItem { product, quantity } => match as_conflict(add_item_impl(*product, quantity))? {
+            Ok(cart_product_hash) => Ok((Some(cart_product_hash), None)),
+            Err(conflict) => Ok((None, Some(conflict))),
+        },
+        CartOp::RemoveItem { product_id, quantity } => {
+            let current = get_current_items_impl()?
+                .into_iter()
+                .find(|line| line.product.product_id == product_id)
+                .map(|line| line.quantity);
+            let current = match current {
+                Some(current) => current,
+                None => return Ok((None, Some(CartOpConflict::ItemMissing { product_id }))),
+            };
+            let removed = quantity.min(current);
+            let clamped =
+                (removed < quantity).then_some(CartOpConflict::QuantityClamped { requested: quantity, removed });
+            match as_conflict(remove_item_impl(product_id, removed))? {
+                Ok(cart_product_hash) => Ok((Some(cart_product_hash), clamped)),
+                Err(conflict) => Ok((None, Some(conflict))),
+            }
+        }
+    }
+}
+
+// Replay every pending op in queue order. Each op and its receipt are committed in this one call, so
+// an op is never applied twice - a rerun only picks up ops without a receipt.
+pub(crate) fn replay_cart_ops_impl() -> ExternResult<CartReplayReport> {
+    let mut report = CartReplayReport::default();
+    for QueuedCartOpWithHash { queued_op_hash, queued_op } in get_pending_cart_ops_impl()? {
+        let status = current_status()?.unwrap_or_else(|| "Shopping".to_string());
+        let (cart_product_hash, conflict) = if status != "Shopping" {
+            (None, Some(CartOpConflict::OrderNotEditable { status }))
+        } else {
+            apply_cart_op(queued_op.op)?
+        };
+
+        let applied = cart_product_hash.is_some();
+        create_entry(EntryTypes::CartOpReceipt(CartOpReceipt {
+            op_id: queued_op.op_id.clone(),
+            queued_op_hash,
+            replayed_at: sys_time()?.as_micros() as u64,
+            applied,
+            cart_product_hash,
+            conflict: conflict.clone(),
+        }))?;
+
+        if applied {
+            report.applied.push(queued_op.op_id.clone());
+        }
+        if let Some(conflict) = conflict {
+            report.conflicts.push(CartOpConflictReport { op_id: queued_op.op_id, conflict });
+        }
+    }
+
+    warn!("📴 OFFLINE QUEUE: Replayed {} ops, {} conflicts", report.applied.len(), report.conflicts.len());
+    Ok(report)
+}
@@ -0,0 +1,192 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::cart::{
+    current_status, find_public_record, get_public_cart_path, get_session_status_impl, next_session_status,
+    release_delivery_time_slot, write_session_status_entry,
+};
+use crate::delivery_proof::{create_delivery_proof, DeliveryProofInput};
+use crate::fulfillment::is_pickup_order;
+use crate::notifications::{send_notification, NotificationDraft};
+use crate::payment::release_payment_for_cancelled_order;
+use crate::properties::get_cart_properties;
+use crate::signals::{notify_agents, RemoteCartSignal};
+
+// Get the shopper assignment for this cart session, if the order has been claimed
+pub(crate) fn get_order_assignment_impl() -> ExternResult<Option<(Record, OrderAssignment)>> {
+    find_public_record::<OrderAssignment>()
+}
+
+// Helper to require an assignment and return it with its hash
+pub(crate) fn require_order_assignment() -> ExternResult<(ActionHash, OrderAssignment)> {
+    match get_order_assignment_impl()? {
+        Some((record, assignment)) => Ok((record.action_address().clone(), assignment)),
+        None => Err(wasm_error!(WasmErrorInner::Guest("Order has not been claimed".to_string()))),
+    }
+}
+
+// Shopper claims a published order - creates the OrderAssignment and moves the status to "Claimed"
+pub(crate) fn claim_order_impl() -> ExternResult<ActionHash> {
+    let shopper = agent_info()?.agent_initial_pubkey;
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    let status_record = get_session_status_impl()?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Order has not been published".to_string())))?;
+    let status = SessionStatus::try_from(status_record.clone())?;
+    if status.status != "Checkout" {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Only orders in Checkout can be claimed (status is {})", status.status
+        ))));
+    }
+    if get_order_assignment_impl()?.is_some() {
+        return Err(wasm_error!(WasmErrorInner::Guest("Order has already been claimed".to_string())));
+    }
+
+    let assignment = OrderAssignment {
+        customer: status_record.action().author().clone(),
+        shopper,
+        published_status_hash: status_record.action_address().clone(),
+        claimed_at: sys_time()?.as_micros() as u64,
+    };
+
+    warn!("🙋 CLAIM ORDER: Shopper {:?} claiming order of {:?}", assignment.shopper, assignment.customer);
+
+    let customer = assignment.customer.clone();
+    let assignment_hash = create_entry(EntryTypes::OrderAssignment(assignment))?;
+    create_link(
+        public_hash,
+        assignment_hash.clone(),
+        LinkTypes::PublicPathToCartData,
+        ()
+    )?;
+
+    write_session_status_entry(SessionStatus {
+        assignment_hash: Some(assignment_hash.clone()),
+        ..next_session_status("Claimed")?
+    })?;
+
+    // Publish the shopper's X25519 key so the customer can share the sealed delivery details
+    crate::encryption::ensure_encryption_key()?;
+
+    send_notification(
+        NotificationDraft {
+            kind: NotificationKind::OrderClaimed,
+            title: "Your order has a shopper".to_string(),
+            body: "A shopper has claimed your order".to_string(),
+            dedupe_key: format!("claimed:{}", assignment_hash),
+            subject_hash: Some(assignment_hash.clone()),
+        },
+        customer,
+    );
+
+    Ok(assignment_hash)
+}
+
+// Assigned shopper marks the order as delivered - the proof is written first and referenced by the status
+pub(crate) fn mark_order_delivered_impl(proof: DeliveryProofInput) -> ExternResult<ActionHash> {
+    let (assignment_hash, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can mark the order delivered".to_string()
+        )));
+    }
+    let status = current_status()?;
+    if status.as_deref() == Some("Delivered") {
+        return Err(wasm_error!(WasmErrorInner::Guest("Order is already delivered".to_string())));
+    }
+    // Pickup orders are handed over once the customer has been told they are ready
+    if is_pickup_order()? && status.as_deref() != Some("ReadyForPickup") {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Mark the order ready for pickup before handing it over".to_string()
+        )));
+    }
+
+    let proof_hash = create_delivery_proof(assignment_hash.clone(), &assignment, proof)?;
+
+    warn!("📦 MARK DELIVERED: Order delivered by {:?}", assignment.shopper);
+    let status_hash = write_session_status_entry(SessionStatus {
+        delivery_proof_hash: Some(proof_hash.clone()),
+        assignment_hash: Some(assignment_hash),
+        ..next_session_status("Delivered")?
+    })?;
+
+    send_notification(
+        NotificationDraft {
+            kind: NotificationKind::OrderDelivered,
+            title: "Your order was delivered".to_string(),
+            body: "Your shopper has handed over your order".to_string(),
+            dedupe_key: format!("delivered:{}", proof_hash),
+            subject_hash: Some(proof_hash),
+        },
+        assignment.customer,
+    );
+
+    Ok(status_hash)
+}
+
+// Cancel the order - the customer until the slot cut-off, the assigned shopper at any point before delivery.
+// Any authorization is voided (or capture refunded), the slot is released and the other party is told.
+pub(crate) fn cancel_order_impl(reason: CancellationReason, note: Option<String>) -> ExternResult<ActionHash> {
+    let me = agent_info()?.agent_initial_pubkey;
+
+    let status = current_status()?.unwrap_or_else(|| "Shopping".to_string());
+    if !is_allowed_status_transition(&status, "Cancelled") {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "An order in {} cannot be cancelled", status
+        ))));
+    }
+
+    let assignment = get_order_assignment_impl()?;
+    let is_shopper = assignment.as_ref().is_some_and(|(_, assignment)| assignment.shopper == me);
+    if reason.is_shopper_reason() && !is_shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Only the assigned shopper can cancel with reason {:?}", reason
+        ))));
+    }
+    if is_shopper && !reason.allowed_for_shopper() {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Shoppers cannot cancel with reason {:?} - ask the customer to cancel instead", reason
+        ))));
+    }
+    if !is_shopper {
+        if let Some((_, time_slot)) = find_public_record::<DeliveryTimeSlot>()? {
+            let cutoff_hours = get_cart_properties()?.order_cutoff_hours.unwrap_or(DEFAULT_ORDER_CUTOFF_HOURS);
+            let now = sys_time()?.as_millis() as u64;
+            if now + cutoff_hours * 60 * 60 * 1000 > time_slot.date {
+                return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                    "Orders can only be cancelled up to {} hours before the delivery slot - message your shopper instead",
+                    cutoff_hours
+                ))));
+            }
+        }
+    }
+
+    // Leave a refund on the customer's own payment intent - a void when nothing was captured yet
+    release_payment_for_cancelled_order()?;
+
+    release_delivery_time_slot()?;
+
+    let assignment_hash = assignment.as_ref().map(|(record, _)| record.action_address().clone());
+    let cancellation = OrderCancellation {
+        cancelled_by: me.clone(),
+        reason,
+        note,
+        assignment_hash: assignment_hash.clone(),
+        cancelled_at: sys_time()?.as_micros() as u64,
+    };
+
+    warn!("🛑 CANCEL ORDER: {:?} cancelled by {:?}", cancellation.reason, me);
+    let status_hash = write_session_status_entry(SessionStatus {
+        cancellation: Some(cancellation.clone()),
+        assignment_hash,
+        ..next_session_status("Cancelled")?
+    })?;
+
+    if let Some((_, assignment)) = assignment {
+        let other_party = if is_shopper { assignment.customer } else { assignment.shopper };
+        notify_agents(RemoteCartSignal::OrderCancelled { cancellation }, vec![other_party]);
+    }
+
+    Ok(status_hash)
+}
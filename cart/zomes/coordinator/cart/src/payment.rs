@@ -0,0 +1,326 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+use crate::cart::{find_public_record, get_current_items_impl, get_public_cart_path, get_session_status_impl};
+use crate::money::{cart_currency, difference_or_zero};
+use crate::picking::estimated_cart_total;
+
+pub const DEFAULT_PAYMENT_PROVIDER: &str = "mock";
+
+// Extra hold on top of the cart estimate so weighed items can come in heavier than ordered
+pub const AUTHORIZATION_BUFFER: f64 = 0.15;
+
+// Result of a provider call
+pub(crate) enum ProviderOutcome {
+    Approved { reference: String },
+    Declined { reason: String },
+}
+
+// Payment provider abstraction - each provider maps intent operations onto its own API
+pub(crate) trait PaymentProvider {
+    fn name(&self) -> &'static str;
+    fn authorize(&self, intent: &PaymentIntent) -> ExternResult<ProviderOutcome>;
+    fn capture(&self, intent: &PaymentIntent, amount: &Money) -> ExternResult<ProviderOutcome>;
+    fn refund(&self, intent: &PaymentIntent, amount: &Money) -> ExternResult<ProviderOutcome>;
+}
+
+// Deterministic mock provider - approves anything up to MOCK_AUTHORIZATION_LIMIT,
+// references are derived from the operation and amount so replays give identical results
+pub(crate) struct MockPaymentProvider;
+
+impl MockPaymentProvider {
+    // In minor units (5000.00)
+    pub const MOCK_AUTHORIZATION_LIMIT: i64 = 500_000;
+
+    fn reference(operation: &str, amount: &Money) -> ExternResult<String> {
+        let digest = hash_blake2b(
+            format!("{}:{}:{}", operation, amount.amount_minor, amount.currency).into_bytes(),
+            8,
+        )?;
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        Ok(format!("mock_{}_{}", operation, hex))
+    }
+}
+
+impl PaymentProvider for MockPaymentProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn authorize(&self, intent: &PaymentIntent) -> ExternResult<ProviderOutcome> {
+        if intent.authorized_amount.amount_minor <= 0 {
+            return Ok(ProviderOutcome::Declined { reason: "Amount must be positive".to_string() });
+        }
+        if intent.authorized_amount.amount_minor > Self::MOCK_AUTHORIZATION_LIMIT {
+            return Ok(ProviderOutcome::Declined { reason: "Amount exceeds mock authorization limit".to_string() });
+        }
+        Ok(ProviderOutcome::Approved { reference: Self::reference("auth", &intent.authorized_amount)? })
+    }
+
+    fn capture(&self, intent: &PaymentIntent, amount: &Money) -> ExternResult<ProviderOutcome> {
+        if amount.currency != intent.currency || amount.amount_minor > intent.authorized_amount.amount_minor {
+            return Ok(ProviderOutcome::Declined { reason: "Capture exceeds authorized amount".to_string() });
+        }
+        Ok(ProviderOutcome::Approved { reference: Self::reference("capture", amount)? })
+    }
+
+    fn refund(&self, intent: &PaymentIntent, amount: &Money) -> ExternResult<ProviderOutcome> {
+        let refundable = refundable_amount(intent)?;
+        if amount.currency != intent.currency || amount.amount_minor > refundable.amount_minor {
+            return Ok(ProviderOutcome::Declined { reason: "Refund exceeds captured amount".to_string() });
+        }
+        Ok(ProviderOutcome::Approved { reference: Self::reference("refund", amount)? })
+    }
+}
+
+// Captured minus already refunded
+pub(crate) fn refundable_amount(intent: &PaymentIntent) -> ExternResult<Money> {
+    let zero = Money::zero(&intent.currency);
+    difference_or_zero(
+        intent.captured_amount.as_ref().unwrap_or(&zero),
+        intent.refunded_amount.as_ref().unwrap_or(&zero),
+    )
+}
+
+// Resolve a provider by name
+pub(crate) fn payment_provider(name: &str) -> ExternResult<Box<dyn PaymentProvider>> {
+    match name {
+        "mock" => Ok(Box::new(MockPaymentProvider)),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!("Unknown payment provider: {}", other)))),
+    }
+}
+
+// Get the current payment intent for this cart session
+pub(crate) fn get_payment_intent_impl() -> ExternResult<Option<(Record, PaymentIntent)>> {
+    find_public_record::<PaymentIntent>()
+}
+
+fn require_payment_intent() -> ExternResult<(Record, PaymentIntent)> {
+    get_payment_intent_impl()?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("No payment intent for this order".to_string())))
+}
+
+// Update the payment intent in place and move the public path link to the new version
+fn write_payment_intent(previous: &Record, payment_intent: PaymentIntent) -> ExternResult<ActionHash> {
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    let new_hash = update_entry(previous.action_address().clone(), payment_intent)?;
+
+    let links = get_links(
+        GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
+    )?;
+    for link in links {
+        if let Some(target_hash) = link.target.clone().into_action_hash() {
+            if target_hash == *previous.action_address() {
+                delete_link(link.create_link_hash)?;
+                break;
+            }
+        }
+    }
+    create_link(public_hash, new_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
+
+    Ok(new_hash)
+}
+
+// Create a payment intent for the current cart estimate (plus weight buffer)
+pub(crate) fn create_payment_intent_impl(provider: Option<String>) -> ExternResult<ActionHash> {
+    let provider = payment_provider(provider.as_deref().unwrap_or(DEFAULT_PAYMENT_PROVIDER))?;
+    let public_path = get_public_cart_path()?;
+    let public_hash = public_path.path_entry_hash()?;
+
+    // A failed or refunded intent can be replaced; a live one cannot
+    if let Some((record, existing)) = get_payment_intent_impl()? {
+        match existing.payment_status {
+            PaymentStatus::Failed | PaymentStatus::Refunded => {
+                let links = get_links(
+                    GetLinksInputBuilder::try_new(public_hash.clone(), LinkTypes::PublicPathToCartData)?.build()
+                )?;
+                for link in links {
+                    if link.target.clone().into_action_hash().as_ref() == Some(record.action_address()) {
+                        delete_link(link.create_link_hash)?;
+                        break;
+                    }
+                }
+            }
+            _ => {
+                return Err(wasm_error!(WasmErrorInner::Guest(
+                    "A payment intent already exists for this order - release it with refund_payment first".to_string()
+                )));
+            }
+        }
+    }
+
+    let items = get_current_items_impl()?;
+    let estimate = estimated_cart_total(&items)?;
+    let payment_intent = PaymentIntent {
+        payment_status: PaymentStatus::Created,
+        provider: provider.name().to_string(),
+        provider_reference: None,
+        currency: cart_currency(&items),
+        authorized_amount: estimate.times(1.0 + AUTHORIZATION_BUFFER),
+        captured_amount: None,
+        refunded_amount: None,
+        failure_reason: None,
+        updated_at: sys_time()?.as_micros() as u64,
+        capture_status_hash: None,
+    };
+
+    warn!("💳 PAYMENT: Creating {} intent for {:?}", payment_intent.provider, payment_intent.authorized_amount);
+
+    let intent_hash = create_entry(EntryTypes::PaymentIntent(payment_intent))?;
+    create_link(public_hash, intent_hash.clone(), LinkTypes::PublicPathToCartData, ())?;
+
+    Ok(intent_hash)
+}
+
+// Authorize the current payment intent with its provider
+pub(crate) fn authorize_payment_impl() -> ExternResult<ActionHash> {
+    let (record, mut payment_intent) = require_payment_intent()?;
+    if payment_intent.payment_status != PaymentStatus::Created {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot authorize a payment in status {:?}", payment_intent.payment_status
+        ))));
+    }
+
+    let provider = payment_provider(&payment_intent.provider)?;
+    match provider.authorize(&payment_intent)? {
+        ProviderOutcome::Approved { reference } => {
+            payment_intent.payment_status = PaymentStatus::Authorized;
+            payment_intent.provider_reference = Some(reference);
+        }
+        ProviderOutcome::Declined { reason } => {
+            payment_intent.payment_status = PaymentStatus::Failed;
+            payment_intent.failure_reason = Some(reason);
+        }
+    }
+    payment_intent.updated_at = sys_time()?.as_micros() as u64;
+
+    warn!("💳 PAYMENT: Authorization result {:?}", payment_intent.payment_status);
+    write_payment_intent(&record, payment_intent)
+}
+
+// Capture the delivered total the shopper stated in the delivery proof once the order is delivered
+pub(crate) fn capture_payment_impl() -> ExternResult<ActionHash> {
+    let status_record = get_session_status_impl()?
+        .filter(|record| SessionStatus::try_from(record.clone()).is_ok_and(|status| status.status == "Delivered"))
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Payment can only be captured after delivery".to_string()
+        )))?;
+
+    let (record, mut payment_intent) = require_payment_intent()?;
+    if payment_intent.payment_status != PaymentStatus::Authorized {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Cannot capture a payment in status {:?}", payment_intent.payment_status
+        ))));
+    }
+
+    let amount = find_public_record::<DeliveryProof>()?
+        .and_then(|(_, proof)| proof.delivered_total)
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "The delivery proof does not state a delivered total".to_string()
+        )))?;
+    let provider = payment_provider(&payment_intent.provider)?;
+    match provider.capture(&payment_intent, &amount)? {
+        ProviderOutcome::Approved { reference } => {
+            payment_intent.payment_status = PaymentStatus::Captured;
+            payment_intent.captured_amount = Some(amount.clone());
+            payment_intent.provider_reference = Some(reference);
+            payment_intent.capture_status_hash = Some(status_record.action_address().clone());
+        }
+        ProviderOutcome::Declined { reason } => {
+            payment_intent.payment_status = PaymentStatus::Failed;
+            payment_intent.failure_reason = Some(reason);
+        }
+    }
+    payment_intent.updated_at = sys_time()?.as_micros() as u64;
+
+    warn!("💳 PAYMENT: Capture of {:?} result {:?}", amount, payment_intent.payment_status);
+    write_payment_intent(&record, payment_intent)
+}
+
+// Refund part or all of a captured payment, or void an authorization that was never captured
+pub(crate) fn refund_payment_impl(amount: Option<Money>) -> ExternResult<ActionHash> {
+    let (record, mut payment_intent) = require_payment_intent()?;
+    let provider = payment_provider(&payment_intent.provider)?;
+
+    match payment_intent.payment_status.clone() {
+        PaymentStatus::Authorized => {
+            // Nothing captured yet - release the hold
+            payment_intent.payment_status = PaymentStatus::Refunded;
+            payment_intent.refunded_amount = Some(Money::zero(&payment_intent.currency));
+        }
+        PaymentStatus::Captured | PaymentStatus::Refunded => {
+            let already_refunded = payment_intent
+                .refunded_amount
+                .clone()
+                .unwrap_or_else(|| Money::zero(&payment_intent.currency));
+            let amount = match amount {
+                Some(amount) => amount,
+                None => refundable_amount(&payment_intent)?,
+            };
+            let refunded_total = already_refunded.checked_add(&amount).ok_or(wasm_error!(WasmErrorInner::Guest(
+                format!("Refunds must be in the payment's currency ({})", payment_intent.currency)
+            )))?;
+            match provider.refund(&payment_intent, &amount)? {
+                ProviderOutcome::Approved { reference } => {
+                    payment_intent.payment_status = PaymentStatus::Refunded;
+                    payment_intent.refunded_amount = Some(refunded_total);
+                    payment_intent.provider_reference = Some(reference);
+                }
+                ProviderOutcome::Declined { reason } => {
+                    return Err(wasm_error!(WasmErrorInner::Guest(format!("Refund declined: {}", reason))));
+                }
+            }
+        }
+        status => {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Cannot refund a payment in status {:?}", status
+            ))));
+        }
+    }
+    payment_intent.updated_at = sys_time()?.as_micros() as u64;
+
+    warn!("💳 PAYMENT: Refunded {:?}", payment_intent.refunded_amount);
+    write_payment_intent(&record, payment_intent)
+}
+
+// Void the hold (or refund the capture) of a cancelled order - only the customer who authored the
+// intent can, so a shopper's cancellation leaves this to the customer's cell when the signal arrives
+pub(crate) fn release_payment_for_cancelled_order() -> ExternResult<Option<ActionHash>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    match get_payment_intent_impl()? {
+        Some((record, payment_intent))
+            if *record.action().author() == me
+                && matches!(payment_intent.payment_status, PaymentStatus::Authorized | PaymentStatus::Captured) =>
+        {
+            refund_payment_impl(None).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+// publish_order precondition - an authorization must cover the current cart estimate.
+// Returns the intent the published status points to.
+pub(crate) fn require_authorized_payment() -> ExternResult<ActionHash> {
+    let (record, payment_intent) = get_payment_intent_impl()?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Payment must be authorized before publishing the order".to_string()
+        )))?;
+    if payment_intent.payment_status != PaymentStatus::Authorized {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "Payment must be authorized before publishing the order (status is {:?})",
+            payment_intent.payment_status
+        ))));
+    }
+
+    let estimate = estimated_cart_total(&get_current_items_impl()?)?;
+    if estimate.currency != payment_intent.currency
+        || estimate.amount_minor > payment_intent.authorized_amount.amount_minor
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Cart total exceeds the authorized amount - release it with refund_payment and create a new payment intent".to_string()
+        )));
+    }
+    Ok(record.action_address().clone())
+}
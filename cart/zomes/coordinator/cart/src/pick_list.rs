@@ -0,0 +1,147 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::order::require_order_assignment;
+use crate::picking::{get_picked_lines_impl, PickedCartLine};
+
+// Store layout types mirror the products DNA's StoreLayout - the shopper app reads the layout
+// there with get_store_layout and passes it in, as the cart zome never calls the products cell itself
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TemperatureZone {
+    Ambient,
+    Chilled,
+    Frozen,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ScaleStation {
+    Deli,
+    Produce,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AisleLocation {
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub product_type: Option<String>,
+    pub aisle: String,
+    pub section: String,
+    pub walk_order: u32,
+    pub temperature_zone: TemperatureZone,
+    pub scale_station: Option<ScaleStation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoreLayout {
+    pub store_id: String,
+    pub locations: Vec<AisleLocation>,
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PickListLine {
+    pub cart_product_hash: ActionHash,
+    pub product_id: String,
+    pub product_name: String,
+    pub upc: Option<String>,
+    pub quantity: f64,
+    pub aisle: Option<String>, // None when the layout has no location for the product
+    pub section: Option<String>,
+    // WEIGHT items are weighed and labelled before they go in the bag
+    pub needs_scale: bool,
+    pub scale_station: Option<ScaleStation>,
+    pub picked: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PickListZone {
+    pub temperature_zone: TemperatureZone,
+    pub lines: Vec<PickListLine>,
+}
+
+// Zones in walking order, frozen last so it spends the least time out of the freezer
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PickList {
+    pub store_id: Option<String>,
+    pub zones: Vec<PickListZone>,
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+fn matches_optional(location: &Option<String>, product: &Option<String>) -> bool {
+    match (location, product) {
+        (None, _) => true,
+        (Some(location), Some(product)) => same_name(location, product),
+        (Some(_), None) => false,
+    }
+}
+
+// Most specific layout entry for the product: product type, then subcategory, then category
+fn find_location<'a>(layout: &'a StoreLayout, product: &CartProduct) -> Option<&'a AisleLocation> {
+    let category = product.category.as_deref()?;
+    layout
+        .locations
+        .iter()
+        .filter(|location| {
+            same_name(&location.category, category)
+                && matches_optional(&location.subcategory, &product.subcategory)
+                && matches_optional(&location.product_type, &product.product_type)
+        })
+        .max_by_key(|location| location.subcategory.is_some() as u8 + location.product_type.is_some() as u8)
+}
+
+// The order's lines in walking order, grouped by temperature zone - assigned shopper only
+pub(crate) fn get_pick_list_impl(layout: Option<StoreLayout>) -> ExternResult<PickList> {
+    let (_, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can see the pick list".to_string()
+        )));
+    }
+
+    // (zone, walk order, line) - products the layout does not know go at the end of the ambient walk
+    let mut located: Vec<(TemperatureZone, u32, PickListLine)> = get_picked_lines_impl()?
+        .into_iter()
+        .map(|PickedCartLine { line, pick }| {
+            let location = layout.as_ref().and_then(|layout| find_location(layout, &line.product));
+            let needs_scale = line.product.sold_by.as_deref() == Some("WEIGHT");
+            let scale_station = location
+                .filter(|_| needs_scale)
+                .and_then(|location| location.scale_station.clone());
+            (
+                location.map_or(TemperatureZone::Ambient, |location| location.temperature_zone),
+                location.map_or(u32::MAX, |location| location.walk_order),
+                PickListLine {
+                    cart_product_hash: line.action_hash,
+                    product_id: line.product.product_id,
+                    product_name: line.product.product_name,
+                    upc: line.product.upc,
+                    quantity: line.quantity,
+                    aisle: location.map(|location| location.aisle.clone()),
+                    section: location.map(|location| location.section.clone()),
+                    needs_scale,
+                    scale_station,
+                    picked: pick.is_some(),
+                },
+            )
+        })
+        .collect();
+    located.sort_by(|a, b| (a.0, a.1, &a.2.product_name).cmp(&(b.0, b.1, &b.2.product_name)));
+
+    let mut zones: Vec<PickListZone> = Vec::new();
+    for (temperature_zone, _, line) in located {
+        match zones.last_mut() {
+            Some(zone) if zone.temperature_zone == temperature_zone => zone.lines.push(line),
+            _ => zones.push(PickListZone { temperature_zone, lines: vec![line] }),
+        }
+    }
+
+    warn!("🧺 PICK LIST: {} zones for {:?}", zones.len(), layout.as_ref().map(|layout| &layout.store_id));
+    Ok(PickList {
+        store_id: layout.map(|layout| layout.store_id),
+        zones,
+    })
+}
@@ -0,0 +1,137 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::{get_current_items_impl, CartProductWithHash};
+use crate::money::{cart_currency, sum_money};
+use crate::notifications::{send_notification, NotificationDraft};
+use crate::order::{get_order_assignment_impl, require_order_assignment};
+
+// A cart line together with the shopper's latest pick for it (if any)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PickedCartLine {
+    #[serde(flatten)]
+    pub line: CartProductWithHash,
+    pub pick: Option<PickRecord>,
+}
+
+// Price a single unit (or weight unit) of a cart line - promo price wins when present
+pub(crate) fn effective_unit_price(product: &CartProduct) -> &Money {
+    product.promo_price.as_ref().unwrap_or(&product.price_at_checkout)
+}
+
+// Assigned shopper records what was actually picked for a cart line
+pub(crate) fn record_pick_impl(
+    cart_product_hash: ActionHash,
+    picked_quantity: f64,
+    substitute: Option<CartProduct>,
+) -> ExternResult<ActionHash> {
+    let (assignment_hash, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can record picks".to_string()
+        )));
+    }
+
+    warn!("🧺 RECORD PICK: {:?} picked {}", cart_product_hash, picked_quantity);
+    let substitute_name = substitute.as_ref().map(|product| product.product_name.clone());
+    let pick_hash = write_pick(assignment_hash, cart_product_hash, picked_quantity, substitute, None)?;
+
+    // Let the customer review the replacement while the shopper is still in the store
+    if let Some(substitute_name) = substitute_name {
+        send_notification(
+            NotificationDraft {
+                kind: NotificationKind::SubstitutionProposed,
+                title: "Your shopper suggested a substitute".to_string(),
+                body: format!("{} x {} instead of an item you ordered", picked_quantity, substitute_name),
+                dedupe_key: format!("substitution:{}", pick_hash),
+                subject_hash: Some(pick_hash.clone()),
+            },
+            assignment.customer,
+        );
+    }
+
+    Ok(pick_hash)
+}
+
+// Write a pick record and link it from its cart line - callers have checked the shopper
+pub(crate) fn write_pick(
+    assignment_hash: ActionHash,
+    cart_product_hash: ActionHash,
+    picked_quantity: f64,
+    substitute: Option<CartProduct>,
+    scan: Option<BarcodeScan>,
+) -> ExternResult<ActionHash> {
+    let pick_record = PickRecord {
+        assignment_hash,
+        cart_product_hash: cart_product_hash.clone(),
+        picked_quantity,
+        picked_at: sys_time()?.as_micros() as u64,
+        substitute: substitute.map(CartProduct::migrated),
+        scan,
+    };
+
+    let pick_hash = create_entry(EntryTypes::PickRecord(pick_record))?;
+    create_link(cart_product_hash, pick_hash.clone(), LinkTypes::CartProductToPick, ())?;
+
+    Ok(pick_hash)
+}
+
+// Get the latest pick record the assigned shopper made for a cart line
+pub(crate) fn get_latest_pick(
+    cart_product_hash: &ActionHash,
+    assignment_hash: &ActionHash,
+    assignment: &OrderAssignment,
+) -> ExternResult<Option<PickRecord>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(cart_product_hash.clone(), LinkTypes::CartProductToPick)?.build()
+    )?;
+
+    let mut latest: Option<PickRecord> = None;
+    for link in links {
+        if let Some(target_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(target_hash, GetOptions::default())? {
+                if *record.action().author() != assignment.shopper {
+                    continue;
+                }
+                if let Ok(pick) = PickRecord::try_from(record) {
+                    if pick.cart_product_hash != *cart_product_hash || pick.assignment_hash != *assignment_hash {
+                        continue;
+                    }
+                    if latest.as_ref().is_none_or(|current| pick.picked_at >= current.picked_at) {
+                        latest = Some(pick);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(latest)
+}
+
+// Get every current cart line with its latest pick
+pub(crate) fn get_picked_lines_impl() -> ExternResult<Vec<PickedCartLine>> {
+    let assignment = get_order_assignment_impl()?;
+    let mut picked_lines = Vec::new();
+    for line in get_current_items_impl()? {
+        let pick = match &assignment {
+            Some((record, assignment)) => get_latest_pick(&line.action_hash, record.action_address(), assignment)?,
+            None => None,
+        };
+        picked_lines.push(PickedCartLine { line, pick });
+    }
+    Ok(picked_lines)
+}
+
+// Final fulfillment total - what the reconciliation says was delivered
+pub(crate) fn fulfillment_total() -> ExternResult<Money> {
+    Ok(crate::reconciliation::get_order_reconciliation_impl()?.delivered_total)
+}
+
+// Estimated total of the cart as ordered
+pub(crate) fn estimated_cart_total(items: &[CartProductWithHash]) -> ExternResult<Money> {
+    sum_money(
+        items.iter().map(|item| effective_unit_price(&item.product).times(item.quantity)),
+        &cart_currency(items),
+    )
+}
@@ -0,0 +1,15 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+
+pub const DEFAULT_AVERAGE_SPEED_KMH: f64 = 30.0;
+
+// Decode the DNA properties, falling back to defaults when they are absent or malformed
+pub(crate) fn get_cart_properties() -> ExternResult<CartDnaProperties> {
+    match cart_dna_properties() {
+        Ok(properties) => Ok(properties),
+        Err(e) => {
+            warn!("⚙️ CART DNA: Failed to decode DNA properties, using defaults: {:?}", e);
+            Ok(CartDnaProperties::default())
+        }
+    }
+}
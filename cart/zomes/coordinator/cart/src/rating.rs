@@ -0,0 +1,119 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::cart::get_session_status_impl;
+use crate::order::require_order_assignment;
+
+// Aggregate view of the ratings an agent has received
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgentRatingSummary {
+    pub agent: AgentPubKey,
+    pub rating_count: u32,
+    pub average_stars: Option<f64>,
+    pub star_counts: [u32; 5],              // index 0 = 1 star ... index 4 = 5 stars
+    pub tag_counts: BTreeMap<String, u32>,
+}
+
+// Rate the other party of a delivered order - the ratee is derived from the assignment
+pub(crate) fn rate_order_impl(stars: u8, tags: Vec<String>, comment: Option<String>) -> ExternResult<ActionHash> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let (assignment_hash, assignment) = require_order_assignment()?;
+
+    let ratee = if me == assignment.customer {
+        assignment.shopper
+    } else if me == assignment.shopper {
+        assignment.customer
+    } else {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the customer and shopper of this order can rate it".to_string()
+        )));
+    };
+
+    let status_record = get_session_status_impl()?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Order has no status".to_string())))?;
+    let status = SessionStatus::try_from(status_record.clone())?;
+    if status.status != "Delivered" {
+        return Err(wasm_error!(WasmErrorInner::Guest("Only delivered orders can be rated".to_string())));
+    }
+
+    let rating = Rating {
+        assignment_hash,
+        delivered_status_hash: status_record.action_address().clone(),
+        ratee: ratee.clone(),
+        stars,
+        tags,
+        comment,
+        rated_at: sys_time()?.as_micros() as u64,
+    };
+
+    warn!("⭐ RATE ORDER: {:?} rating {:?} with {} stars", me, ratee, stars);
+
+    let rating_hash = create_entry(EntryTypes::Rating(rating))?;
+    create_link(ratee, rating_hash.clone(), LinkTypes::AgentToRating, ())?;
+
+    Ok(rating_hash)
+}
+
+// Get every rating an agent has received - links filed by anyone but the rater, or under another agent, are skipped
+pub(crate) fn get_ratings_for_agent_impl(agent: AgentPubKey) -> ExternResult<Vec<(ActionHash, Rating)>> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::AgentToRating)?.build()
+    )?;
+
+    let mut ratings: Vec<(ActionHash, Rating)> = Vec::new();
+    for link in links {
+        if let Some(target_hash) = link.target.into_action_hash() {
+            if ratings.iter().any(|(hash, _)| *hash == target_hash) {
+                continue;
+            }
+            if let Some(record) = get(target_hash.clone(), GetOptions::default())? {
+                if *record.action().author() != link.author {
+                    continue;
+                }
+                if let Ok(rating) = Rating::try_from(record) {
+                    if rating.ratee == agent {
+                        ratings.push((target_hash, rating));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ratings)
+}
+
+// Aggregate star average, star histogram and tag counts for an agent
+pub(crate) fn get_agent_rating_summary_impl(agent: AgentPubKey) -> ExternResult<AgentRatingSummary> {
+    let ratings = get_ratings_for_agent_impl(agent.clone())?;
+
+    let mut star_counts = [0u32; 5];
+    let mut tag_counts = BTreeMap::new();
+    let mut total_stars = 0u32;
+
+    for (_, rating) in &ratings {
+        if (1..=5).contains(&rating.stars) {
+            star_counts[(rating.stars - 1) as usize] += 1;
+            total_stars += rating.stars as u32;
+        }
+        for tag in &rating.tags {
+            *tag_counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let rating_count = star_counts.iter().sum::<u32>();
+    let average_stars = if rating_count > 0 {
+        Some(total_stars as f64 / rating_count as f64)
+    } else {
+        None
+    };
+
+    Ok(AgentRatingSummary {
+        agent,
+        rating_count,
+        average_stars,
+        star_counts,
+        tag_counts,
+    })
+}
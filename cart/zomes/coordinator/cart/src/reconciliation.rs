@@ -0,0 +1,138 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::money::{difference_or_zero, sum_money};
+use crate::payment::{get_payment_intent_impl, refundable_amount};
+use crate::picking::{effective_unit_price, get_picked_lines_impl, PickedCartLine};
+
+// How a cart line compares to what the shopper actually picked
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LineOutcome {
+    DeliveredAsOrdered,
+    WeightAdjusted,
+    Substituted,
+    PartiallyFilled,
+    Missing,
+}
+
+// One line of the reconciliation report
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReconciledLine {
+    pub cart_product_hash: ActionHash,
+    pub product_id: String,
+    pub product_name: String,
+    pub outcome: LineOutcome,
+    pub ordered_quantity: f64,
+    pub delivered_quantity: f64,
+    pub ordered_amount: Money,
+    pub delivered_amount: Money,
+    // delivered_amount - ordered_amount; negative means the customer owes less
+    pub amount_difference: Money,
+    pub substitute: Option<CartProduct>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderReconciliation {
+    pub lines: Vec<ReconciledLine>,
+    pub ordered_total: Money,
+    pub delivered_total: Money,
+    // Amount owed back to the customer - against the captured amount once captured, the ordered total before that
+    pub refund_due: Money,
+}
+
+fn is_sold_by_weight(product: &CartProduct) -> bool {
+    product.sold_by.as_deref() == Some("WEIGHT")
+}
+
+// Compare one ordered line with its latest pick
+fn reconcile_line(picked: &PickedCartLine) -> ExternResult<ReconciledLine> {
+    let product = &picked.line.product;
+    let ordered_quantity = picked.line.quantity;
+    let ordered_amount = effective_unit_price(product).times(ordered_quantity);
+    let currency = ordered_amount.currency.clone();
+
+    let (outcome, delivered_quantity, delivered_amount, substitute) = match &picked.pick {
+        // No pick recorded - the line is taken as delivered as ordered
+        None => (LineOutcome::DeliveredAsOrdered, ordered_quantity, ordered_amount.clone(), None),
+        Some(pick) if pick.picked_quantity <= 0.0 => (LineOutcome::Missing, 0.0, Money::zero(&currency), None),
+        Some(pick) => match &pick.substitute {
+            Some(substitute) => (
+                LineOutcome::Substituted,
+                pick.picked_quantity,
+                effective_unit_price(substitute).times(pick.picked_quantity),
+                Some(substitute.clone()),
+            ),
+            None if is_sold_by_weight(product) => {
+                let outcome = if (pick.picked_quantity - ordered_quantity).abs() < f64::EPSILON {
+                    LineOutcome::DeliveredAsOrdered
+                } else {
+                    LineOutcome::WeightAdjusted
+                };
+                (outcome, pick.picked_quantity, effective_unit_price(product).times(pick.picked_quantity), None)
+            }
+            None => {
+                // Unit items are never charged above the ordered count
+                let quantity = pick.picked_quantity.min(ordered_quantity);
+                let outcome = if quantity < ordered_quantity {
+                    LineOutcome::PartiallyFilled
+                } else {
+                    LineOutcome::DeliveredAsOrdered
+                };
+                (outcome, quantity, effective_unit_price(product).times(quantity), None)
+            }
+        },
+    };
+
+    let amount_difference = delivered_amount.checked_sub(&ordered_amount).ok_or(wasm_error!(
+        WasmErrorInner::Guest(format!("Substitute for {} is priced in another currency", product.product_name))
+    ))?;
+
+    Ok(ReconciledLine {
+        cart_product_hash: picked.line.action_hash.clone(),
+        product_id: product.product_id.clone(),
+        product_name: product.product_name.clone(),
+        outcome,
+        ordered_quantity,
+        delivered_quantity,
+        ordered_amount,
+        delivered_amount,
+        amount_difference,
+        substitute,
+    })
+}
+
+// Compare the published cart snapshot with what the shopper picked
+pub(crate) fn get_order_reconciliation_impl() -> ExternResult<OrderReconciliation> {
+    let lines = get_picked_lines_impl()?
+        .iter()
+        .map(reconcile_line)
+        .collect::<ExternResult<Vec<ReconciledLine>>>()?;
+
+    let currency = lines
+        .first()
+        .map(|line| line.ordered_amount.currency.clone())
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string());
+    let ordered_total = sum_money(lines.iter().map(|line| line.ordered_amount.clone()), &currency)?;
+    let delivered_total = sum_money(lines.iter().map(|line| line.delivered_amount.clone()), &currency)?;
+
+    let refund_due = match get_payment_intent_impl()? {
+        Some((_, payment_intent)) if payment_intent.captured_amount.is_some() => {
+            let refundable = refundable_amount(&payment_intent)?;
+            difference_or_zero(&refundable, &delivered_total)?
+        }
+        _ => difference_or_zero(&ordered_total, &delivered_total)?,
+    };
+
+    warn!(
+        "🧾 RECONCILIATION: {} lines, ordered {:?} delivered {:?} refund due {:?}",
+        lines.len(), ordered_total, delivered_total, refund_due
+    );
+
+    Ok(OrderReconciliation {
+        lines,
+        ordered_total,
+        delivered_total,
+        refund_due,
+    })
+}
@@ -0,0 +1,303 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::notifications::{record_notification, NotificationDraft};
+use crate::properties::get_cart_properties;
+use crate::signals::CartSignal;
+use crate::AddCartItemInput;
+
+// How far ahead of the delivery date a template is turned into a cart session
+pub const RECURRING_ORDER_LEAD_DAYS: u64 = 3;
+
+// A template with the hash of its first version - the stable id used by every operation
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecurringOrderWithHash {
+    pub recurring_order_hash: ActionHash,
+    pub recurring_order: RecurringOrder,
+}
+
+// A cart session created from a template, waiting for the customer to confirm (publish) it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MaterializedOrder {
+    pub recurring_order_hash: ActionHash,
+    pub cell_id: CellId,
+    pub delivery_date: u64,
+    pub confirm_by: u64,
+    // Template lines that could not be added - the rest of the order still goes ahead
+    pub skipped_lines: Vec<SkippedRecurringLine>,
+}
+
+// A template line left out of a materialized order, with why
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SkippedRecurringLine {
+    pub product_id: String,
+    pub product_name: String,
+    pub quantity: f64,
+    pub reason: String,
+}
+
+// Create a standing order - call this on the customer's main cart cell, not a session clone
+pub(crate) fn create_recurring_order_impl(
+    lines: Vec<RecurringOrderLine>,
+    cadence: RecurrenceCadence,
+    time_slot: String,
+    address_hash: ActionHash,
+    address: Address,
+    first_delivery_date: u64,
+) -> ExternResult<ActionHash> {
+    let recurring_order = RecurringOrder {
+        lines: lines
+            .into_iter()
+            .map(|line| RecurringOrderLine { product: line.product.migrated(), quantity: line.quantity })
+            .collect(),
+        cadence,
+        time_slot,
+        address_hash,
+        address,
+        next_delivery_date: first_delivery_date,
+        status: RecurringOrderStatus::Active,
+        skip_next: false,
+        last_materialized_at: None,
+        created_at: sys_time()?.as_micros() as u64,
+    };
+
+    warn!("🔁 RECURRING: Creating {:?} order first due {}", recurring_order.cadence, first_delivery_date);
+    create_entry(EntryTypes::RecurringOrder(recurring_order))
+}
+
+// Latest version of every template on this agent's chain, keyed by the first version's hash
+fn latest_recurring_orders() -> ExternResult<BTreeMap<ActionHash, (Record, RecurringOrder)>> {
+    let records = query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::RecurringOrder.try_into()?)
+            .include_entries(true),
+    )?;
+
+    // Chain order guarantees each update is seen after the version it replaces
+    let mut roots: HashMap<ActionHash, ActionHash> = HashMap::new();
+    let mut latest = BTreeMap::new();
+    for record in records {
+        let root = match record.action() {
+            Action::Update(update) => roots
+                .get(&update.original_action_address)
+                .cloned()
+                .unwrap_or_else(|| update.original_action_address.clone()),
+            _ => record.action_address().clone(),
+        };
+        roots.insert(record.action_address().clone(), root.clone());
+        if let Ok(recurring_order) = RecurringOrder::try_from(record.clone()) {
+            latest.insert(root, (record, recurring_order));
+        }
+    }
+    Ok(latest)
+}
+
+pub(crate) fn get_recurring_orders_impl() -> ExternResult<Vec<RecurringOrderWithHash>> {
+    Ok(latest_recurring_orders()?
+        .into_iter()
+        .map(|(recurring_order_hash, (_, recurring_order))| RecurringOrderWithHash {
+            recurring_order_hash,
+            recurring_order,
+        })
+        .collect())
+}
+
+// Apply a change to the latest version of a template
+fn modify_recurring_order(
+    recurring_order_hash: ActionHash,
+    modify: impl FnOnce(&mut RecurringOrder) -> ExternResult<()>,
+) -> ExternResult<ActionHash> {
+    let (record, mut recurring_order) = latest_recurring_orders()?
+        .remove(&recurring_order_hash)
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Recurring order not found".to_string())))?;
+    if recurring_order.status == RecurringOrderStatus::Cancelled {
+        return Err(wasm_error!(WasmErrorInner::Guest("Recurring order has been cancelled".to_string())));
+    }
+    modify(&mut recurring_order)?;
+    update_entry(record.action_address().clone(), recurring_order)
+}
+
+pub(crate) fn pause_recurring_order_impl(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    modify_recurring_order(recurring_order_hash, |recurring_order| {
+        recurring_order.status = RecurringOrderStatus::Paused;
+        Ok(())
+    })
+}
+
+// Resuming moves a stale next date forward so no missed weeks are materialized at once
+pub(crate) fn resume_recurring_order_impl(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    let now = sys_time()?.as_millis() as u64;
+    modify_recurring_order(recurring_order_hash, |recurring_order| {
+        let interval = recurring_order.cadence.interval_millis();
+        while recurring_order.next_delivery_date < now {
+            recurring_order.next_delivery_date += interval;
+        }
+        recurring_order.status = RecurringOrderStatus::Active;
+        Ok(())
+    })
+}
+
+pub(crate) fn skip_next_recurring_order_impl(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    modify_recurring_order(recurring_order_hash, |recurring_order| {
+        recurring_order.skip_next = true;
+        Ok(())
+    })
+}
+
+pub(crate) fn cancel_recurring_order_impl(recurring_order_hash: ActionHash) -> ExternResult<ActionHash> {
+    modify_recurring_order(recurring_order_hash, |recurring_order| {
+        recurring_order.status = RecurringOrderStatus::Cancelled;
+        Ok(())
+    })
+}
+
+// Call a zome function in another cart session cell of this agent
+pub(crate) fn call_session<I>(cell_id: &CellId, fn_name: &str, payload: I) -> ExternResult<()>
+where
+    I: Serialize + std::fmt::Debug,
+{
+    match call(CallTargetCell::OtherCell(cell_id.clone()), zome_info()?.name, fn_name.into(), None, payload)? {
+        ZomeCallResponse::Ok(_) => Ok(()),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "{} failed in the cart session cell: {:?}", fn_name, other
+        )))),
+    }
+}
+
+// Call a zome function in another cart session cell of this agent and decode what it returns
+pub(crate) fn call_session_for<I, O>(cell_id: &CellId, fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    match call(CallTargetCell::OtherCell(cell_id.clone()), zome_info()?.name, fn_name.into(), None, payload)? {
+        ZomeCallResponse::Ok(output) => output.decode().map_err(|err| wasm_error!(err)),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "{} failed in the cart session cell: {:?}", fn_name, other
+        )))),
+    }
+}
+
+// Clone a new cart session for one occurrence and fill it from the template. The template's snapshots
+// carry quotes that expired long before the lead time, so each line is added with the caller's current
+// catalog snapshot for its product (keeping the customer's note); lines without one fall back to the
+// template snapshot, and any line the session refuses is reported instead of failing the whole order.
+fn materialize_occurrence(
+    recurring_order_hash: &ActionHash,
+    recurring_order: &RecurringOrder,
+    confirm_by: u64,
+    catalog_products: &[CartProduct],
+) -> ExternResult<MaterializedOrder> {
+    let delivery_date = recurring_order.next_delivery_date;
+    let cloned_cell = create_clone_cell(CreateCloneCellInput {
+        cell_id: CellId::new(dna_info()?.hash, agent_info()?.agent_initial_pubkey),
+        membrane_proof: None,
+        modifiers: DnaModifiersOpt::none()
+            .with_network_seed(format!("recurring-{}-{}", recurring_order_hash, delivery_date)),
+        name: Some(format!("Recurring order {}", delivery_date)),
+    })?;
+    let cell_id = cloned_cell.cell_id;
+
+    let mut skipped_lines = Vec::new();
+    for line in &recurring_order.lines {
+        let product = match catalog_products.iter().find(|product| product.product_id == line.product.product_id) {
+            Some(current) => CartProduct { note: line.product.note.clone(), ..current.clone() },
+            None => line.product.clone(),
+        };
+        if let Err(err) = call_session(&cell_id, "add_cart_item", AddCartItemInput {
+            product,
+            quantity: line.quantity,
+        }) {
+            warn!("🔁 RECURRING: Could not add {}: {:?}", line.product.product_name, err);
+            skipped_lines.push(SkippedRecurringLine {
+                product_id: line.product.product_id.clone(),
+                product_name: line.product.product_name.clone(),
+                quantity: line.quantity,
+                reason: format!("{:?}", err),
+            });
+        }
+    }
+    call_session(&cell_id, "set_delivery_address", recurring_order.address.clone())?;
+    call_session(&cell_id, "set_delivery_time_slot", DeliveryTimeSlot {
+        date: delivery_date,
+        time_slot: recurring_order.time_slot.clone(),
+    })?;
+
+    Ok(MaterializedOrder {
+        recurring_order_hash: recurring_order_hash.clone(),
+        cell_id,
+        delivery_date,
+        confirm_by,
+        skipped_lines,
+    })
+}
+
+// Turn every due template into a cart session and ask the customer to confirm it before cut-off
+pub(crate) fn materialize_due_orders_impl(catalog_products: Vec<CartProduct>) -> ExternResult<Vec<MaterializedOrder>> {
+    let now = sys_time()?.as_millis() as u64;
+    let cutoff_hours = get_cart_properties()?.order_cutoff_hours.unwrap_or(DEFAULT_ORDER_CUTOFF_HOURS);
+    let cutoff_millis = cutoff_hours * 60 * 60 * 1000;
+    let lead_millis = RECURRING_ORDER_LEAD_DAYS * MILLIS_PER_DAY;
+
+    let mut materialized = Vec::new();
+    for (recurring_order_hash, (record, mut recurring_order)) in latest_recurring_orders()? {
+        if recurring_order.status != RecurringOrderStatus::Active {
+            continue;
+        }
+        let interval = recurring_order.cadence.interval_millis();
+        let mut changed = false;
+
+        // Occurrences whose cut-off already passed are dropped (and use up a pending skip)
+        while now > recurring_order.next_delivery_date.saturating_sub(cutoff_millis) {
+            recurring_order.next_delivery_date += interval;
+            recurring_order.skip_next = false;
+            changed = true;
+        }
+
+        if now + lead_millis >= recurring_order.next_delivery_date {
+            if recurring_order.skip_next {
+                warn!("🔁 RECURRING: Skipping occurrence on {}", recurring_order.next_delivery_date);
+                recurring_order.skip_next = false;
+            } else {
+                let confirm_by = recurring_order.next_delivery_date - cutoff_millis;
+                let order = materialize_occurrence(&recurring_order_hash, &recurring_order, confirm_by, &catalog_products)?;
+                warn!("🔁 RECURRING: Materialized order for {} in {:?}", order.delivery_date, order.cell_id);
+                emit_signal(CartSignal::RecurringOrderReady {
+                    recurring_order_hash: recurring_order_hash.clone(),
+                    cell_id: order.cell_id.clone(),
+                    delivery_date: order.delivery_date,
+                    confirm_by: order.confirm_by,
+                })?;
+                // Kept in the template's cell, where the customer manages their recurring orders
+                record_notification(
+                    NotificationDraft {
+                        kind: NotificationKind::RecurringOrderAwaitingConfirmation,
+                        title: "Your recurring order is ready to confirm".to_string(),
+                        body: match order.skipped_lines.len() {
+                            0 => format!("Publish it before {} to keep the delivery slot", order.confirm_by),
+                            skipped => format!(
+                                "Publish it before {} to keep the delivery slot - {} items could not be added",
+                                order.confirm_by, skipped
+                            ),
+                        },
+                        dedupe_key: format!("recurring:{}:{}", recurring_order_hash, order.delivery_date),
+                        subject_hash: Some(recurring_order_hash.clone()),
+                    },
+                    None,
+                )?;
+                recurring_order.last_materialized_at = Some(sys_time()?.as_micros() as u64);
+                materialized.push(order);
+            }
+            recurring_order.next_delivery_date += interval;
+            changed = true;
+        }
+
+        if changed {
+            update_entry(record.action_address().clone(), recurring_order)?;
+        }
+    }
+
+    Ok(materialized)
+}
@@ -0,0 +1,117 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::get_current_items_impl;
+use crate::order::require_order_assignment;
+use crate::picking::{effective_unit_price, get_latest_pick, write_pick};
+
+// Weights worked out from a label price are kept to the gram
+const WEIGHT_DECIMALS: f64 = 1000.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ScanOutcome {
+    // Same GTIN as the cart line - one more unit picked
+    Match,
+    // Price-embedded label for the line's item code - the weight comes from the label price
+    RandomWeightMatch { weight: f64, price: Money },
+    // Possible wrong item - nothing is recorded
+    Mismatch { expected_gtin: String, scanned_gtin: String },
+    // Check digit or length is wrong - rescan
+    InvalidBarcode { scanned: String },
+    // The line was added without a barcode, so the scan cannot be checked
+    NoBarcodeOnFile { scanned_gtin: String },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScanVerification {
+    pub outcome: ScanOutcome,
+    pub pick_hash: Option<ActionHash>, // Set when the scan was recorded as a pick
+}
+
+fn is_sold_by_weight(product: &CartProduct) -> bool {
+    product.sold_by.as_deref() == Some("WEIGHT")
+}
+
+// Check a scanned barcode against a cart line. A match adds to the line's latest pick (one unit,
+// or the label weight for price-embedded barcodes); a mismatch is only reported.
+pub(crate) fn verify_scan_impl(cart_product_hash: ActionHash, scanned_upc: String) -> ExternResult<ScanVerification> {
+    let (assignment_hash, assignment) = require_order_assignment()?;
+    if agent_info()?.agent_initial_pubkey != assignment.shopper {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only the assigned shopper can scan items".to_string()
+        )));
+    }
+    let line = get_current_items_impl()?
+        .into_iter()
+        .find(|line| line.action_hash == cart_product_hash)
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Cart line not found".to_string())))?;
+
+    let scanned_gtin = match normalize_gtin(&scanned_upc) {
+        Some(gtin) => gtin,
+        None => {
+            return Ok(ScanVerification {
+                outcome: ScanOutcome::InvalidBarcode { scanned: scanned_upc },
+                pick_hash: None,
+            });
+        }
+    };
+    let expected_gtin = match line.product.upc.as_deref().and_then(normalize_gtin) {
+        Some(gtin) => gtin,
+        None => {
+            return Ok(ScanVerification {
+                outcome: ScanOutcome::NoBarcodeOnFile { scanned_gtin },
+                pick_hash: None,
+            });
+        }
+    };
+
+    // Scans add to what was already picked - a substituted pick starts again from the scan
+    let previous_quantity = get_latest_pick(&cart_product_hash, &assignment_hash, &assignment)?
+        .filter(|pick| pick.substitute.is_none())
+        .map_or(0.0, |pick| pick.picked_quantity);
+
+    let random_weight = parse_random_weight(&scanned_gtin).filter(|label| {
+        parse_random_weight(&expected_gtin).is_some_and(|expected| expected.item_code == label.item_code)
+    });
+
+    let (outcome, picked_quantity, scan) = if let Some(label) = random_weight {
+        let unit_price = effective_unit_price(&line.product);
+        if unit_price.amount_minor <= 0 {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "{} has no price to work out the label weight from", line.product.product_name
+            ))));
+        }
+        let price = Money::new(label.price_minor, &unit_price.currency);
+        let weight = (label.price_minor as f64 / unit_price.amount_minor as f64 * WEIGHT_DECIMALS).round()
+            / WEIGHT_DECIMALS;
+        (
+            ScanOutcome::RandomWeightMatch { weight, price: price.clone() },
+            previous_quantity + weight,
+            BarcodeScan { gtin: scanned_gtin, embedded_price: Some(price), embedded_weight: Some(weight) },
+        )
+    } else if scanned_gtin == expected_gtin && !is_sold_by_weight(&line.product) {
+        (
+            ScanOutcome::Match,
+            previous_quantity + 1.0,
+            BarcodeScan { gtin: scanned_gtin, embedded_price: None, embedded_weight: None },
+        )
+    } else if scanned_gtin == expected_gtin {
+        // Fixed barcode on a weighed item - the shopper still enters the weight with record_pick
+        return Ok(ScanVerification { outcome: ScanOutcome::Match, pick_hash: None });
+    } else {
+        warn!(
+            "🧺 SCAN: Possible wrong item for {} - expected {}, scanned {}",
+            line.product.product_name, expected_gtin, scanned_gtin
+        );
+        return Ok(ScanVerification {
+            outcome: ScanOutcome::Mismatch { expected_gtin, scanned_gtin },
+            pick_hash: None,
+        });
+    };
+
+    warn!("🧺 SCAN: {:?} for {}, picked {}", outcome, line.product.product_name, picked_quantity);
+    let pick_hash = write_pick(assignment_hash, cart_product_hash, picked_quantity, None, Some(scan))?;
+    Ok(ScanVerification { outcome, pick_hash: Some(pick_hash) })
+}
@@ -0,0 +1,312 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::add_item_impl;
+use crate::recurring::call_session;
+use crate::AddCartItemInput;
+
+// A list with the hash of its first version (the stable id) and its current version
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShoppingListWithHash {
+    pub list_hash: ActionHash,
+    pub version_hash: ActionHash,
+    pub shopping_list: ShoppingList,
+}
+
+// A list item keyed by its current version (the one the list links to)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShoppingListItemWithHash {
+    pub item_hash: ActionHash,
+    pub item: ShoppingListItem,
+}
+
+// Outcome of turning a list into cart lines
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConvertListResult {
+    pub added: Vec<ActionHash>,                      // Items that became cart lines (now checked off)
+    pub unresolved: Vec<ShoppingListItemWithHash>,   // Free-text items that matched no catalog product
+}
+
+// Follow an entry's updates to its newest version
+fn get_latest_version(hash: ActionHash) -> ExternResult<Option<Record>> {
+    let mut current = hash;
+    loop {
+        match get_details(current.clone(), GetOptions::default())? {
+            Some(Details::Record(details)) => {
+                match details.updates.iter().max_by_key(|update| update.action().timestamp()) {
+                    Some(update) => current = update.hashed.hash.clone(),
+                    None => return Ok(Some(details.record)),
+                }
+            }
+            _ => return Ok(None),
+        }
+    }
+}
+
+fn get_shopping_list(list_hash: &ActionHash) -> ExternResult<ShoppingListWithHash> {
+    let record = get_latest_version(list_hash.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Shopping list not found".to_string())))?;
+    Ok(ShoppingListWithHash {
+        list_hash: list_hash.clone(),
+        version_hash: record.action_address().clone(),
+        shopping_list: ShoppingList::try_from(record)?,
+    })
+}
+
+// The owner's chain head - items cite it so validators read the same list version
+fn get_owner_chain_top(owner: &AgentPubKey) -> ExternResult<ActionHash> {
+    let me = agent_info()?;
+    if *owner == me.agent_initial_pubkey {
+        return Ok(me.chain_head.0);
+    }
+    get_agent_activity(owner.clone(), ChainQueryFilter::new(), ActivityRequest::Status)?
+        .highest_observed
+        .and_then(|observed| observed.hash.into_iter().next())
+        .ok_or(wasm_error!(WasmErrorInner::Guest("The list owner's chain could not be reached".to_string())))
+}
+
+// Owner or member check before writing items - against the current version on the owner's chain,
+// returned with the chain head it was read from
+fn require_list_editor(list_hash: &ActionHash) -> ExternResult<(ShoppingListWithHash, ActionHash)> {
+    let root = get(list_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Shopping list not found".to_string())))?;
+    let owner = ShoppingList::try_from(root)?.owner;
+    let owner_chain_top = get_owner_chain_top(&owner)?;
+    let version_hash = latest_list_version(owner, owner_chain_top.clone(), list_hash)?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Shopping list not found on its owner's chain".to_string())))?;
+    let version = get(version_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Shopping list version not found".to_string())))?;
+    let shopping_list = ShoppingList::try_from(version)?;
+
+    if !shopping_list.can_edit(&agent_info()?.agent_initial_pubkey) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "This shopping list has not been shared with you".to_string()
+        )));
+    }
+    Ok((ShoppingListWithHash { list_hash: list_hash.clone(), version_hash, shopping_list }, owner_chain_top))
+}
+
+// "2 lemons" -> (2.0, "lemons"); anything without a leading number counts as one
+fn parse_quantity(text: &str) -> (f64, String) {
+    let trimmed = text.trim();
+    if let Some((first, rest)) = trimmed.split_once(char::is_whitespace) {
+        if let Ok(quantity) = first.parse::<f64>() {
+            if quantity.is_finite() && quantity > 0.0 {
+                return (quantity, rest.trim().to_string());
+            }
+        }
+    }
+    (1.0, trimmed.to_string())
+}
+
+pub(crate) fn create_shopping_list_impl(name: String) -> ExternResult<ActionHash> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let list_hash = create_entry(EntryTypes::ShoppingList(ShoppingList {
+        name,
+        owner: me.clone(),
+        members: Vec::new(),
+        updated_at: sys_time()?.as_micros() as u64,
+    }))?;
+    create_link(me, list_hash.clone(), LinkTypes::AgentToShoppingList, ())?;
+
+    warn!("📝 SHOPPING LIST: Created {:?}", list_hash);
+    Ok(list_hash)
+}
+
+// Lists this agent owns or that were shared with them
+pub(crate) fn get_my_shopping_lists_impl() -> ExternResult<Vec<ShoppingListWithHash>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let links = get_links(GetLinksInputBuilder::try_new(me.clone(), LinkTypes::AgentToShoppingList)?.build())?;
+
+    let mut lists = Vec::new();
+    for link in links {
+        if let Some(list_hash) = link.target.into_action_hash() {
+            if let Ok(list) = get_shopping_list(&list_hash) {
+                // Shares that were revoked leave a stale link behind
+                if list.shopping_list.can_edit(&me) {
+                    lists.push(list);
+                }
+            }
+        }
+    }
+    Ok(lists)
+}
+
+// Owner adds or removes a member
+pub(crate) fn set_shopping_list_member_impl(
+    list_hash: ActionHash,
+    agent: AgentPubKey,
+    shared: bool,
+) -> ExternResult<ActionHash> {
+    let mut list = get_shopping_list(&list_hash)?;
+    if list.shopping_list.owner != agent_info()?.agent_initial_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest("Only the owner can share a shopping list".to_string())));
+    }
+
+    list.shopping_list.members.retain(|member| *member != agent);
+    if shared {
+        list.shopping_list.members.push(agent.clone());
+        create_link(agent.clone(), list_hash.clone(), LinkTypes::AgentToShoppingList, ())?;
+    } else {
+        let links = get_links(GetLinksInputBuilder::try_new(agent.clone(), LinkTypes::AgentToShoppingList)?.build())?;
+        for link in links {
+            if link.target.clone().into_action_hash().as_ref() == Some(&list_hash) {
+                delete_link(link.create_link_hash)?;
+            }
+        }
+    }
+    list.shopping_list.updated_at = sys_time()?.as_micros() as u64;
+
+    warn!("📝 SHOPPING LIST: {} {:?}", if shared { "Shared with" } else { "Unshared from" }, agent);
+    update_entry(list.version_hash, list.shopping_list)
+}
+
+pub(crate) fn add_shopping_list_item_impl(
+    list_hash: ActionHash,
+    text: String,
+    quantity: Option<f64>,
+    product: Option<CartProduct>,
+) -> ExternResult<ActionHash> {
+    let (list, owner_chain_top) = require_list_editor(&list_hash)?;
+    let (parsed_quantity, parsed_text) = parse_quantity(&text);
+
+    let item = ShoppingListItem {
+        list_hash: list_hash.clone(),
+        list_version_hash: list.version_hash,
+        owner_chain_top,
+        text: if quantity.is_some() { text.trim().to_string() } else { parsed_text },
+        quantity: quantity.unwrap_or(parsed_quantity),
+        product: product.map(CartProduct::migrated),
+        checked: false,
+        checked_by: None,
+        updated_at: sys_time()?.as_micros() as u64,
+    };
+
+    let item_hash = create_entry(EntryTypes::ShoppingListItem(item))?;
+    create_link(list_hash, item_hash.clone(), LinkTypes::ShoppingListToItem, ())?;
+    Ok(item_hash)
+}
+
+pub(crate) fn get_shopping_list_items_impl(list_hash: ActionHash) -> ExternResult<Vec<ShoppingListItemWithHash>> {
+    let links = get_links(GetLinksInputBuilder::try_new(list_hash.clone(), LinkTypes::ShoppingListToItem)?.build())?;
+
+    let mut items = Vec::new();
+    for link in links {
+        if let Some(item_hash) = link.target.into_action_hash() {
+            if let Some(record) = get(item_hash.clone(), GetOptions::default())? {
+                if let Ok(item) = ShoppingListItem::try_from(record) {
+                    // Items of other lists linked here are not ours to show
+                    if item.list_hash == list_hash {
+                        items.push(ShoppingListItemWithHash { item_hash, item });
+                    }
+                }
+            }
+        }
+    }
+    items.sort_by_key(|entry| entry.item.updated_at);
+    Ok(items)
+}
+
+// Move the list's link from one item version to the next
+fn relink_item(list_hash: &ActionHash, previous: &ActionHash, next: &ActionHash) -> ExternResult<()> {
+    let links = get_links(GetLinksInputBuilder::try_new(list_hash.clone(), LinkTypes::ShoppingListToItem)?.build())?;
+    for link in links {
+        if link.target.clone().into_action_hash().as_ref() == Some(previous) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    create_link(list_hash.clone(), next.clone(), LinkTypes::ShoppingListToItem, ())?;
+    Ok(())
+}
+
+// Check an item off (or back on) - returns the item's new hash
+pub(crate) fn set_shopping_list_item_checked_impl(item_hash: ActionHash, checked: bool) -> ExternResult<ActionHash> {
+    let record = get(item_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Shopping list item not found".to_string())))?;
+    let mut item = ShoppingListItem::try_from(record)?;
+    let (list, owner_chain_top) = require_list_editor(&item.list_hash)?;
+
+    item.list_version_hash = list.version_hash;
+    item.owner_chain_top = owner_chain_top;
+    item.checked = checked;
+    item.checked_by = if checked { Some(agent_info()?.agent_initial_pubkey) } else { None };
+    item.updated_at = sys_time()?.as_micros() as u64;
+
+    let list_hash = item.list_hash.clone();
+    let new_hash = update_entry(item_hash.clone(), item)?;
+    relink_item(&list_hash, &item_hash, &new_hash)?;
+    Ok(new_hash)
+}
+
+pub(crate) fn remove_shopping_list_item_impl(item_hash: ActionHash) -> ExternResult<()> {
+    let record = get(item_hash.clone(), GetOptions::default())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest("Shopping list item not found".to_string())))?;
+    let item = ShoppingListItem::try_from(record)?;
+    require_list_editor(&item.list_hash)?;
+
+    let links = get_links(GetLinksInputBuilder::try_new(item.list_hash, LinkTypes::ShoppingListToItem)?.build())?;
+    for link in links {
+        if link.target.clone().into_action_hash().as_ref() == Some(&item_hash) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    Ok(())
+}
+
+// Match an item to a catalog product by UPC first, then product id - the item's own snapshot is the fallback
+fn resolve_item(item: &ShoppingListItem, catalog_products: &[CartProduct]) -> Option<CartProduct> {
+    let (upc, product_id) = match &item.product {
+        Some(product) => (product.upc.clone(), Some(product.product_id.clone())),
+        // A free-text item may itself be a scanned or typed code
+        None => (Some(item.text.clone()), Some(item.text.clone())),
+    };
+
+    let by_upc = upc.as_ref().and_then(|upc| {
+        catalog_products.iter().find(|product| product.upc.as_ref() == Some(upc))
+    });
+    let by_product_id = product_id.as_ref().and_then(|product_id| {
+        catalog_products.iter().find(|product| product.product_id == *product_id)
+    });
+
+    by_upc.or(by_product_id).cloned().or_else(|| item.product.clone())
+}
+
+// Add every unchecked, resolvable item to a cart through the normal add path and check it off.
+// Catalog snapshots come from the caller (the products DNA is not reachable from here); the cart
+// is this cell unless session_cell_id names another cart session of the same agent.
+pub(crate) fn convert_list_to_cart_impl(
+    list_hash: ActionHash,
+    catalog_products: Vec<CartProduct>,
+    session_cell_id: Option<CellId>,
+) -> ExternResult<ConvertListResult> {
+    require_list_editor(&list_hash)?;
+
+    let mut result = ConvertListResult { added: Vec::new(), unresolved: Vec::new() };
+    for entry in get_shopping_list_items_impl(list_hash)? {
+        if entry.item.checked {
+            continue;
+        }
+        let product = match resolve_item(&entry.item, &catalog_products) {
+            Some(product) => product,
+            None => {
+                result.unresolved.push(entry);
+                continue;
+            }
+        };
+
+        match &session_cell_id {
+            Some(cell_id) => call_session(cell_id, "add_cart_item", AddCartItemInput {
+                product,
+                quantity: entry.item.quantity,
+            })?,
+            None => {
+                add_item_impl(product, entry.item.quantity)?;
+            }
+        }
+        result.added.push(set_shopping_list_item_checked_impl(entry.item_hash, true)?);
+    }
+
+    warn!("📝 SHOPPING LIST: Converted {} items, {} unresolved", result.added.len(), result.unresolved.len());
+    Ok(result)
+}
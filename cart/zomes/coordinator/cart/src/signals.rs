@@ -0,0 +1,196 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cart::current_status;
+use crate::encryption::open_envelopes;
+use crate::location::ShopperPosition;
+use crate::notifications::{record_notification, NotificationDraft};
+use crate::order::get_order_assignment_impl;
+use crate::payment::release_payment_for_cancelled_order;
+
+// Payloads sent agent-to-agent with send_remote_signal
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum RemoteCartSignal {
+    ChatMessage {
+        message_hash: ActionHash,
+        message: OrderMessage,
+    },
+    MessagesRead {
+        message_hashes: Vec<ActionHash>,
+        read_at: u64,
+    },
+    ShopperLocation {
+        position: ShopperPosition,
+    },
+    OrderCancelled {
+        cancellation: OrderCancellation,
+    },
+    OrderReadyForPickup {
+        store: StoreLocation,
+    },
+    CustomerCheckedIn {
+        bay: Option<String>,
+        note: Option<String>,
+        checked_in_at: u64,
+    },
+    Notification {
+        notification: NotificationDraft,
+    },
+}
+
+// Signals emitted to this agent's own UI
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum CartSignal {
+    ChatMessageReceived {
+        message_hash: ActionHash,
+        from: AgentPubKey,
+        content: ChatMessageContent,
+        sent_at: u64,
+    },
+    MessagesRead {
+        from: AgentPubKey,
+        message_hashes: Vec<ActionHash>,
+        read_at: u64,
+    },
+    ShopperLocationUpdated {
+        from: AgentPubKey,
+        position: ShopperPosition,
+    },
+    OrderCancelled {
+        from: AgentPubKey,
+        cancellation: OrderCancellation,
+    },
+    OrderReadyForPickup {
+        from: AgentPubKey,
+        store: StoreLocation,
+    },
+    // The customer is waiting at the store
+    CustomerCheckedIn {
+        from: AgentPubKey,
+        bay: Option<String>,
+        note: Option<String>,
+        checked_in_at: u64,
+    },
+    // A recurring order was turned into a cart session - publish it before confirm_by
+    RecurringOrderReady {
+        recurring_order_hash: ActionHash,
+        cell_id: CellId,
+        delivery_date: u64,
+        confirm_by: u64,
+    },
+    // A notification was stored on this agent's chain
+    Notification {
+        notification_hash: ActionHash,
+        notification: Notification,
+    },
+}
+
+// Allow other agents in the cell to deliver remote signals to us
+pub(crate) fn grant_remote_signal_capability() -> ExternResult<()> {
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, "recv_remote_signal".into()));
+
+    create_cap_grant(CapGrantEntry {
+        tag: "remote_signals".into(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    Ok(())
+}
+
+// Fire-and-forget delivery - a missing peer must never fail the calling zome function
+pub(crate) fn notify_agents(signal: RemoteCartSignal, agents: Vec<AgentPubKey>) {
+    if let Err(err) = send_remote_signal(signal, agents) {
+        warn!("📡 SIGNAL: Failed to send remote signal: {:?}", err);
+    }
+}
+
+// The assignment of this cell's order - a claim notification can arrive before its link is visible,
+// so its subject is used when that is an OrderAssignment of ours in this cell
+fn notification_assignment(draft: &NotificationDraft) -> ExternResult<Option<OrderAssignment>> {
+    if let Some((_, assignment)) = get_order_assignment_impl()? {
+        return Ok(Some(assignment));
+    }
+    let subject_hash = match &draft.subject_hash {
+        Some(hash) if draft.kind == NotificationKind::OrderClaimed => hash.clone(),
+        _ => return Ok(None),
+    };
+    let me = agent_info()?.agent_initial_pubkey;
+    Ok(get(subject_hash, GetOptions::default())?
+        .and_then(|record| OrderAssignment::try_from(record).ok())
+        .filter(|assignment| assignment.customer == me))
+}
+
+// Whether `from` may send this signal - only the parties of this cell's order are heard,
+// and position and pickup updates come from the shopper, check-ins from the customer
+fn is_expected_sender(signal: &RemoteCartSignal, from: &AgentPubKey) -> ExternResult<bool> {
+    let assignment = match signal {
+        RemoteCartSignal::Notification { notification } => notification_assignment(notification)?,
+        _ => get_order_assignment_impl()?.map(|(_, assignment)| assignment),
+    };
+    Ok(assignment.is_some_and(|assignment| match signal {
+        RemoteCartSignal::ShopperLocation { .. } | RemoteCartSignal::OrderReadyForPickup { .. } => {
+            *from == assignment.shopper
+        }
+        RemoteCartSignal::CustomerCheckedIn { .. } => *from == assignment.customer,
+        _ => *from == assignment.customer || *from == assignment.shopper,
+    }))
+}
+
+// Turn an incoming remote signal into a local UI signal, opening sealed content on the way
+pub(crate) fn handle_remote_signal(signal: RemoteCartSignal) -> ExternResult<()> {
+    let from = call_info()?.provenance;
+
+    // Anyone holding the cell can signal us
+    if !is_expected_sender(&signal, &from)? {
+        warn!("📡 SIGNAL: Dropped a signal from {:?} - not the expected party of this order", from);
+        return Ok(());
+    }
+
+    match signal {
+        RemoteCartSignal::ChatMessage { message_hash, message } => {
+            let content: Option<ChatMessageContent> = open_envelopes(&message.sender_key, &message.envelopes)?;
+            if let Some(content) = content {
+                emit_signal(CartSignal::ChatMessageReceived {
+                    message_hash,
+                    from,
+                    content,
+                    sent_at: message.sent_at,
+                })?;
+            }
+        }
+        RemoteCartSignal::MessagesRead { message_hashes, read_at } => {
+            emit_signal(CartSignal::MessagesRead {
+                from,
+                message_hashes,
+                read_at,
+            })?;
+        }
+        RemoteCartSignal::ShopperLocation { position } => {
+            emit_signal(CartSignal::ShopperLocationUpdated { from, position })?;
+        }
+        RemoteCartSignal::OrderCancelled { cancellation } => {
+            // The shopper cannot touch our payment intent - release it once the cancellation is on the DHT
+            if current_status()?.as_deref() == Some("Cancelled") {
+                release_payment_for_cancelled_order()?;
+            }
+            emit_signal(CartSignal::OrderCancelled { from, cancellation })?;
+        }
+        RemoteCartSignal::OrderReadyForPickup { store } => {
+            emit_signal(CartSignal::OrderReadyForPickup { from, store })?;
+        }
+        RemoteCartSignal::CustomerCheckedIn { bay, note, checked_in_at } => {
+            emit_signal(CartSignal::CustomerCheckedIn { from, bay, note, checked_in_at })?;
+        }
+        RemoteCartSignal::Notification { notification } => {
+            // Stored before it is signalled, so it shows up in get_notifications too
+            record_notification(notification, Some(from))?;
+        }
+    }
+
+    Ok(())
+}
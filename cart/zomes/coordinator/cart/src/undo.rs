@@ -0,0 +1,223 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::cart::{current_status, get_public_cart_path};
+
+// A cart line's quantity before and after an undo or restore
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CartLineChange {
+    pub cart_product_hash: ActionHash,
+    pub product_id: String,
+    pub product_name: String,
+    pub from: f64,
+    pub to: f64,
+}
+
+// One quantity link of a cart line, deleted or live
+struct QuantityLink {
+    create_link_hash: ActionHash,
+    quantity: f64,
+    created_at: u64,
+    prev_action: ActionHash,
+    deleted_at: Option<u64>,
+    delete_hash: Option<ActionHash>,
+}
+
+impl QuantityLink {
+    // A link replaces the previous one when it was written right after that link's delete, in the same
+    // change - as in history.rs
+    fn replaces(&self, previous: &QuantityLink) -> bool {
+        previous.delete_hash.as_ref() == Some(&self.prev_action)
+    }
+}
+
+struct CartLineLinks {
+    product: CartProduct,
+    links: Vec<QuantityLink>, // Oldest first
+}
+
+impl CartLineLinks {
+    fn live_link(&self) -> Option<&QuantityLink> {
+        self.links.iter().rev().find(|link| link.deleted_at.is_none())
+    }
+
+    // When this line last changed - a removal is the deletion of its last link
+    fn last_changed_at(&self) -> u64 {
+        self.links
+            .last()
+            .map_or(0, |link| link.deleted_at.unwrap_or(link.created_at).max(link.created_at))
+    }
+}
+
+fn require_shopping() -> ExternResult<()> {
+    if let Some(status) = current_status()? {
+        if status != "Shopping" {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Cart changes can only be undone while shopping (status is {})", status
+            ))));
+        }
+    }
+    Ok(())
+}
+
+// Every CartProduct this agent ever linked from the public path with its quantity links - removals only
+// delete the link, so the entry (with its note and price) and its last quantity are still there.
+// The path is shared with the shopper's data (and anyone can link to it), so other authors are skipped.
+fn cart_line_links() -> ExternResult<HashMap<ActionHash, CartLineLinks>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    let public_hash = get_public_cart_path()?.path_entry_hash()?;
+    let details = get_link_details(public_hash, LinkTypes::PublicPathToCartData, None, GetOptions::default())?;
+
+    let mut lines: HashMap<ActionHash, CartLineLinks> = HashMap::new();
+    for (create, deletes) in details.into_inner() {
+        let create_link = match create.action() {
+            Action::CreateLink(create_link) => create_link.clone(),
+            _ => continue,
+        };
+        if create_link.author != me {
+            continue;
+        }
+        let target_hash = match create_link.target_address.into_action_hash() {
+            Some(hash) => hash,
+            None => continue,
+        };
+        if !lines.contains_key(&target_hash) {
+            let product = match get(target_hash.clone(), GetOptions::default())? {
+                Some(record) if *record.action().author() == me => match CartProduct::try_from(record) {
+                    Ok(product) => product,
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+            lines.insert(target_hash.clone(), CartLineLinks { product, links: Vec::new() });
+        }
+
+        let (quantity, _) = CartQuantityTag::from_link_tag(&create_link.tag);
+        let first_delete = deletes.iter().min_by_key(|delete| delete.action().timestamp());
+        let deleted_at = first_delete.map(|delete| delete.action().timestamp().as_micros() as u64);
+        let delete_hash = first_delete.map(|delete| delete.action_address().clone());
+        if let Some(line) = lines.get_mut(&target_hash) {
+            line.links.push(QuantityLink {
+                create_link_hash: create.action_address().clone(),
+                quantity,
+                created_at: create_link.timestamp.as_micros() as u64,
+                prev_action: create_link.prev_action.clone(),
+                deleted_at,
+                delete_hash,
+            });
+        }
+    }
+
+    for line in lines.values_mut() {
+        line.links.sort_by_key(|link| link.created_at);
+    }
+    Ok(lines)
+}
+
+// Re-linking an old CartProduct skips the quote check its create went through, so on networks with a
+// price_quote_signer the quote it carries must still be unexpired now
+fn require_unexpired_quote(product: &CartProduct) -> ExternResult<()> {
+    if cart_dna_properties()?.price_quote_signer.is_none() {
+        return Ok(());
+    }
+    let expires_at = product.price_quote.as_ref().map_or(0, |signed| signed.quote.expires_at);
+    if sys_time()?.as_micros() as u64 > expires_at {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "The price of {} has expired - add it to the cart again", product.product_name
+        ))));
+    }
+    Ok(())
+}
+
+// Replace a line's live quantity link (if any) with one for the given quantity - 0 removes the line
+fn set_line_quantity(cart_product_hash: &ActionHash, line: &CartLineLinks, quantity: f64) -> ExternResult<()> {
+    if quantity > 0.0 {
+        require_unexpired_quote(&line.product)?;
+    }
+    if let Some(live_link) = line.live_link() {
+        delete_link(live_link.create_link_hash.clone())?;
+    }
+    if quantity > 0.0 {
+        let quantity_tag = CartQuantityTag {
+            quantity,
+            timestamp: sys_time()?.as_micros() as u64,
+        };
+        create_link(
+            get_public_cart_path()?.path_entry_hash()?,
+            cart_product_hash.clone(),
+            LinkTypes::PublicPathToCartData,
+            quantity_tag.to_link_tag(),
+        )?;
+    }
+    Ok(())
+}
+
+// Revert the most recent add, quantity change or removal. The undo is itself a change, so calling
+// this again redoes it.
+pub(crate) fn undo_last_cart_change_impl() -> ExternResult<Option<CartLineChange>> {
+    require_shopping()?;
+    let lines = cart_line_links()?;
+    let (cart_product_hash, line) = match lines.iter().max_by_key(|(_, line)| line.last_changed_at()) {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+    let last_link = match line.links.last() {
+        Some(link) => link,
+        None => return Ok(None),
+    };
+
+    let (from, to) = if last_link.deleted_at.is_some() {
+        // Removed - bring the line back at its last quantity
+        (0.0, last_link.quantity)
+    } else {
+        // Added or changed - go back to the link it replaced, or out of the cart if it was (re-)added
+        let previous = line
+            .links
+            .len()
+            .checked_sub(2)
+            .map(|index| &line.links[index])
+            .filter(|previous| last_link.replaces(previous))
+            .map_or(0.0, |previous| previous.quantity);
+        (last_link.quantity, previous)
+    };
+
+    warn!("↩️ UNDO: {} from {} back to {}", line.product.product_name, from, to);
+    set_line_quantity(cart_product_hash, line, to)?;
+    Ok(Some(CartLineChange {
+        cart_product_hash: cart_product_hash.clone(),
+        product_id: line.product.product_id.clone(),
+        product_name: line.product.product_name.clone(),
+        from,
+        to,
+    }))
+}
+
+// Put a removed product back with the quantity (and snapshot) it had when it was removed
+pub(crate) fn restore_removed_item_impl(product_id: String) -> ExternResult<CartLineChange> {
+    require_shopping()?;
+    let lines = cart_line_links()?;
+    let matching: Vec<(&ActionHash, &CartLineLinks)> =
+        lines.iter().filter(|(_, line)| line.product.product_id == product_id).collect();
+    if matching.iter().any(|(_, line)| line.live_link().is_some()) {
+        return Err(wasm_error!(WasmErrorInner::Guest(format!("{} is already in the cart", product_id))));
+    }
+
+    let (cart_product_hash, line) = matching
+        .into_iter()
+        .filter(|(_, line)| !line.links.is_empty())
+        .max_by_key(|(_, line)| line.last_changed_at())
+        .ok_or(wasm_error!(WasmErrorInner::Guest(format!("{} was never in this cart", product_id))))?;
+    let quantity = line.links.last().map_or(0.0, |link| link.quantity);
+
+    warn!("↩️ RESTORE: {} at {}", line.product.product_name, quantity);
+    set_line_quantity(cart_product_hash, line, quantity)?;
+    Ok(CartLineChange {
+        cart_product_hash: cart_product_hash.clone(),
+        product_id,
+        product_name: line.product.product_name.clone(),
+        from: 0.0,
+        to: quantity,
+    })
+}
@@ -0,0 +1,172 @@
+use cart_integrity::*;
+use hdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Optional coordinator zome: re-checks the cart against the catalog before publishing. It calls the
+// products cell by role name, so it only does that work in apps that install both DNAs - shopper
+// apps that carry cart_dna alone keep publishing through the cart zome as before.
+pub const PRODUCTS_ROLE: &str = "products";
+pub const PRODUCTS_ZOME: &str = "product_catalog";
+pub const CART_ZOME: &str = "cart";
+
+// Catalog fields the checkout needs - products_integrity::Product has more, which are ignored
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CatalogProduct {
+    pub name: String,
+    pub price: Money,
+    pub promo_price: Option<Money>,
+    pub stocks_status: String,
+}
+
+// Cart line fields read back from the cart zome's get_current_items
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CartLine {
+    #[serde(flatten)]
+    pub product: CartProduct,
+    pub action_hash: ActionHash,
+    pub quantity: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum CatalogProblem {
+    // The ref no longer points at a catalog product
+    ProductNotFound { product_id: String, product_name: String },
+    OutOfStock { product_id: String, product_name: String },
+    // The catalog price moved since the line was added - re-add it to take the new price
+    PriceChanged {
+        product_id: String,
+        product_name: String,
+        cart_price: Money,
+        cart_promo_price: Option<Money>,
+        catalog_price: Money,
+        catalog_promo_price: Option<Money>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrchestratedCheckout {
+    // False when no products cell was reachable and the catalog checks were skipped
+    pub catalog_checked: bool,
+    pub problems: Vec<CatalogProblem>,
+    // The published status, when the order was published
+    pub status_hash: Option<ActionHash>,
+}
+
+// Input struct for publishing through the orchestrator - mirrors the cart zome's CheckoutInput
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CheckoutInput {
+    #[serde(default)]
+    pub age_confirmed: bool,
+}
+
+#[hdk_extern]
+pub fn init() -> ExternResult<InitCallbackResult> {
+    Ok(InitCallbackResult::Pass)
+}
+
+fn call_cart<I, O>(fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    match call(CallTargetCell::Local, CART_ZOME, fn_name.into(), None, payload)? {
+        ZomeCallResponse::Ok(output) => output.decode().map_err(|err| wasm_error!(err)),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "{} failed in the cart zome: {:?}", fn_name, other
+        )))),
+    }
+}
+
+// Resolve a cart product id in the products cell. Err(None) means there is no products cell to ask.
+fn resolve_in_catalog(product_id: &str) -> Result<Option<CatalogProduct>, Option<WasmError>> {
+    let response = call(
+        CallTargetCell::OtherRole(PRODUCTS_ROLE.into()),
+        PRODUCTS_ZOME,
+        "resolve_product_id".into(),
+        None,
+        product_id.to_string(),
+    );
+    match response {
+        Ok(ZomeCallResponse::Ok(output)) => output.decode().map_err(|err| Some(wasm_error!(err))),
+        Ok(other) => Err(Some(wasm_error!(WasmErrorInner::Guest(format!(
+            "resolve_product_id failed in the products cell: {:?}", other
+        ))))),
+        Err(err) if is_missing_role(&err) => {
+            warn!("🛒 ORCHESTRATOR: No products cell to check against: {:?}", err);
+            Err(None)
+        }
+        Err(err) => Err(Some(err)),
+    }
+}
+
+// The conductor's answer when the app has no cell for the role - any other call error is a real failure
+fn is_missing_role(err: &WasmError) -> bool {
+    matches!(&err.error, WasmErrorInner::Host(message) if message.to_lowercase().contains("role not found"))
+}
+
+fn is_out_of_stock(stocks_status: &str) -> bool {
+    let normalized: String = stocks_status.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    normalized.eq_ignore_ascii_case("outofstock") || normalized.eq_ignore_ascii_case("unavailable")
+}
+
+fn check_line(line: &CartLine, catalog_product: Option<CatalogProduct>) -> Option<CatalogProblem> {
+    let product = &line.product;
+    let catalog_product = match catalog_product {
+        Some(catalog_product) => catalog_product,
+        None => {
+            return Some(CatalogProblem::ProductNotFound {
+                product_id: product.product_id.clone(),
+                product_name: product.product_name.clone(),
+            });
+        }
+    };
+    if is_out_of_stock(&catalog_product.stocks_status) {
+        return Some(CatalogProblem::OutOfStock {
+            product_id: product.product_id.clone(),
+            product_name: product.product_name.clone(),
+        });
+    }
+    if catalog_product.price != product.price_at_checkout || catalog_product.promo_price != product.promo_price {
+        return Some(CatalogProblem::PriceChanged {
+            product_id: product.product_id.clone(),
+            product_name: product.product_name.clone(),
+            cart_price: product.price_at_checkout.clone(),
+            cart_promo_price: product.promo_price.clone(),
+            catalog_price: catalog_product.price,
+            catalog_promo_price: catalog_product.promo_price,
+        });
+    }
+    None
+}
+
+// Re-resolve every cart line in the catalog, then publish through the cart zome when nothing is
+// stale. Without a products cell the order is published on the cart zome's own checks.
+#[hdk_extern]
+pub fn publish_checked_order(input: Option<CheckoutInput>) -> ExternResult<OrchestratedCheckout> {
+    let input = input.unwrap_or_default();
+    let lines: Vec<CartLine> = call_cart("get_current_items", ())?;
+
+    let mut catalog_checked = true;
+    let mut problems = Vec::new();
+    for line in &lines {
+        match resolve_in_catalog(&line.product.product_id) {
+            Ok(catalog_product) => problems.extend(check_line(line, catalog_product)),
+            Err(Some(err)) => return Err(err),
+            Err(None) => {
+                catalog_checked = false;
+                problems.clear();
+                break;
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        warn!("🛒 ORCHESTRATOR: {} cart lines are stale, not publishing", problems.len());
+        return Ok(OrchestratedCheckout { catalog_checked, problems, status_hash: None });
+    }
+
+    let status_hash: ActionHash = call_cart("publish_order", Some(input))?;
+    warn!("🛒 ORCHESTRATOR: Published {} lines (catalog checked: {})", lines.len(), catalog_checked);
+    Ok(OrchestratedCheckout { catalog_checked, problems, status_hash: Some(status_hash) })
+}
@@ -12,4 +12,7 @@ pub struct Address {
     pub lng: f64,
     pub is_default: bool,
     pub label: Option<String>, // "Home", "Work", etc.
+    // Random salt behind the sealed entry's location_commitment - set when sealing, never by the UI
+    #[serde(default)]
+    pub location_salt: Option<Vec<u8>>,
 }
\ No newline at end of file
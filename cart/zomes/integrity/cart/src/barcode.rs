@@ -0,0 +1,150 @@
+use hdi::prelude::*;
+
+use crate::Money;
+
+// Barcodes are compared as 14-digit GTINs - UPC-A (12) and EAN-13 are left-padded with zeros
+pub const GTIN_LENGTH: usize = 14;
+
+// What a shopper scanned for a pick - weight and price are set for price-embedded labels
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BarcodeScan {
+    pub gtin: String,
+    #[serde(default)]
+    pub embedded_price: Option<Money>,
+    #[serde(default)]
+    pub embedded_weight: Option<f64>,
+}
+
+// In-store random-weight label (prefix 2) - the item code plus the price printed on the label
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RandomWeightBarcode {
+    pub item_code: String,
+    pub price_minor: i64,
+}
+
+// GS1 mod-10 check digit for the digits before it
+pub fn gtin_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| *digit as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+// Normalize a scanned or stored code to a GTIN-14. Accepts UPC-A, EAN-13 and GTIN-14 with a
+// valid check digit, and 11-digit UPC-A stored without one (the check digit is added).
+pub fn normalize_gtin(raw: &str) -> Option<String> {
+    let cleaned: String = raw.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+    if cleaned.is_empty() || !cleaned.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut digits: Vec<u8> = cleaned.bytes().map(|b| b - b'0').collect();
+    match digits.len() {
+        11 => {
+            let check = gtin_check_digit(&digits);
+            digits.push(check);
+        }
+        12..=GTIN_LENGTH => {
+            let (body, check) = digits.split_at(digits.len() - 1);
+            if gtin_check_digit(body) != check[0] {
+                return None;
+            }
+        }
+        _ => return None,
+    }
+
+    let padding = GTIN_LENGTH - digits.len();
+    Some("0".repeat(padding) + &digits.iter().map(|d| (b'0' + d) as char).collect::<String>())
+}
+
+// Read a price-embedded label from a normalized GTIN-14:
+// UPC-A   0 2 IIIII V PPPP C  - 5-digit item code, price verifier, 4-digit price
+// EAN-13  2 X IIIII PPPPP C   - prefixes 20-29, 5-digit item code, 5-digit price
+pub fn parse_random_weight(gtin: &str) -> Option<RandomWeightBarcode> {
+    if gtin.len() != GTIN_LENGTH || !gtin.starts_with('0') {
+        return None;
+    }
+    let gtin13 = &gtin[1..];
+    let (item_code, price) = if gtin13.starts_with("02") {
+        (&gtin13[2..7], &gtin13[8..12])
+    } else if gtin13.starts_with('2') {
+        (&gtin13[2..7], &gtin13[7..12])
+    } else {
+        return None;
+    };
+    Some(RandomWeightBarcode {
+        item_code: format!("{}{}", &gtin13[..2], item_code),
+        price_minor: price.parse().ok()?,
+    })
+}
+
+pub fn validate_barcode_scan(scan: &BarcodeScan) -> Option<String> {
+    if normalize_gtin(&scan.gtin).as_deref() != Some(scan.gtin.as_str()) {
+        return Some("Scanned barcode must be stored as a valid GTIN-14".to_string());
+    }
+    if scan.embedded_price.as_ref().is_some_and(|price| price.is_negative()) {
+        return Some("Embedded price cannot be negative".to_string());
+    }
+    if scan.embedded_weight.is_some_and(|weight| !weight.is_finite() || weight <= 0.0) {
+        return Some("Embedded weight must be a positive number".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_gtin_pads_upc_and_ean_to_gtin14() {
+        assert_eq!(normalize_gtin("036000291452").as_deref(), Some("00036000291452"));
+        assert_eq!(normalize_gtin("4006381333931").as_deref(), Some("04006381333931"));
+        assert_eq!(normalize_gtin("00036000291452").as_deref(), Some("00036000291452"));
+    }
+
+    #[test]
+    fn normalize_gtin_adds_the_missing_upc_check_digit() {
+        assert_eq!(normalize_gtin("03600029145").as_deref(), Some("00036000291452"));
+    }
+
+    #[test]
+    fn normalize_gtin_ignores_spaces_and_dashes() {
+        assert_eq!(normalize_gtin(" 0 36000-29145 2 ").as_deref(), Some("00036000291452"));
+    }
+
+    #[test]
+    fn normalize_gtin_rejects_bad_codes() {
+        assert_eq!(normalize_gtin("036000291453"), None); // Wrong check digit
+        assert_eq!(normalize_gtin("03600029145X"), None);
+        assert_eq!(normalize_gtin("0360002914"), None);
+        assert_eq!(normalize_gtin("000360002914520"), None);
+        assert_eq!(normalize_gtin(""), None);
+    }
+
+    #[test]
+    fn parse_random_weight_reads_upc_labels() {
+        let gtin = normalize_gtin("212345605998").unwrap();
+        assert_eq!(
+            parse_random_weight(&gtin),
+            Some(RandomWeightBarcode { item_code: "0212345".to_string(), price_minor: 599 })
+        );
+    }
+
+    #[test]
+    fn parse_random_weight_reads_ean_labels() {
+        let gtin = normalize_gtin("2012345012349").unwrap();
+        assert_eq!(
+            parse_random_weight(&gtin),
+            Some(RandomWeightBarcode { item_code: "2012345".to_string(), price_minor: 1234 })
+        );
+    }
+
+    #[test]
+    fn parse_random_weight_skips_regular_and_unnormalized_codes() {
+        assert_eq!(parse_random_weight("00036000291452"), None);
+        assert_eq!(parse_random_weight("212345605998"), None);
+        assert_eq!(parse_random_weight("12012345012349"), None);
+    }
+}
@@ -0,0 +1,48 @@
+use hdi::prelude::*;
+
+// Most orders a shopper can carry in one store trip
+pub const MAX_BATCH_ORDERS: usize = 4;
+
+// One order in a batch - the order's cart session is the shopper's cell for this DNA hash
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchOrder {
+    pub dna_hash: DnaHash,
+    pub assignment_hash: ActionHash,
+}
+
+// Shopper's multi-order trip - PRIVATE entry on the shopper's main cart cell
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct ShopperBatch {
+    pub orders: Vec<BatchOrder>,
+    // Delivery orders' assignment hashes in driving order (pickup orders have no stop)
+    pub stop_order: Vec<ActionHash>,
+    pub created_at: u64,
+}
+
+pub fn validate_create_shopper_batch(
+    _action: EntryCreationAction,
+    batch: ShopperBatch,
+) -> ExternResult<ValidateCallbackResult> {
+    if batch.orders.len() < 2 || batch.orders.len() > MAX_BATCH_ORDERS {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "A batch holds between 2 and {} orders",
+            MAX_BATCH_ORDERS
+        )));
+    }
+    for (index, order) in batch.orders.iter().enumerate() {
+        if batch.orders[..index].iter().any(|other| other.assignment_hash == order.assignment_hash) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "An order can only appear once in a batch".to_string(),
+            ));
+        }
+    }
+    for stop in &batch.stop_order {
+        if !batch.orders.iter().any(|order| order.assignment_hash == *stop) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Every stop must be an order in the batch".to_string(),
+            ));
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
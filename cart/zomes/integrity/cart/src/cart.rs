@@ -1,5 +1,10 @@
 use hdi::prelude::*;
 
+use crate::{
+    validate_cart_product_quote, validate_order_cancellation, validate_product_ref, validate_published_payment_intent,
+    DeliveryProof, Money, OrderAssignment, OrderCancellation, PaymentIntent, SignedPriceQuote, MONEY_SCHEMA_VERSION,
+};
+
 // Link tag structure for storing cart quantity and timestamp data
 // Following the established pattern from products.rs
 pub struct CartQuantityTag {
@@ -44,8 +49,8 @@ impl CartQuantityTag {
 #[hdk_entry_helper]
 #[derive(Clone)]
 pub struct CartProduct {
-    // A unique, permanent string identifier for the product.
-    // This will be created on the frontend as `${group_hash}:${product_index}`.
+    // A unique, permanent string identifier for the product - a serialized ProductRef
+    // (`${group_hash}:${product_index}` plus optional catalog, id and upc parts).
     pub product_id: String,
     pub upc: Option<String>,
 
@@ -54,14 +59,35 @@ pub struct CartProduct {
     pub product_image_url: Option<String>,
 
     // The price is frozen at the time of adding to the cart. This is the source of truth.
-    pub price_at_checkout: f64,
-    pub promo_price: Option<f64>,
+    pub price_at_checkout: Money,
+    pub promo_price: Option<Money>,
+    // Admin-signed quote backing the two prices above - required once the DNA names a price_quote_signer
+    #[serde(default)]
+    pub price_quote: Option<SignedPriceQuote>,
     
     // How the product is sold - "UNIT" or "WEIGHT" - needed for correct increment/decrement behavior
     pub sold_by: Option<String>,
 
+    // Catalog category path - lets the shopper's pick list find the aisle
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub subcategory: Option<String>,
+    #[serde(default)]
+    pub product_type: Option<String>,
+
     // This field will store any snapshotted product preferences or customer notes.
     pub note: Option<String>,
+
+    // Age-restricted products (Beer, Wine, Liquor, Hard Beverages...) need an ID check at the door
+    #[serde(default)]
+    pub age_restricted: bool,
+    // Per-order limit on units (or weight) - enforced on the quantity link tag
+    #[serde(default)]
+    pub max_quantity: Option<f64>,
+    // 0 for legacy snapshots with float prices - Money reads those transparently
+    #[serde(default)]
+    pub schema_version: u32,
     
     // --- CART-SPECIFIC DATA MOVED TO LINK TAGS ---
     // quantity: f64,    // NOW IN LINK TAG via CartQuantityTag
@@ -72,8 +98,430 @@ pub struct CartProduct {
 #[hdk_entry_helper]
 #[derive(Clone)]
 pub struct SessionStatus {
-    pub status: String, // "Building" or "AwaitingShopper"
+    pub status: String, // "Shopping", "Checkout", "Claimed", "ReadyForPickup", "Delivered" or "Cancelled"
     pub last_updated: u64,
+    // Required when status is "Delivered"
+    #[serde(default)]
+    pub delivery_proof_hash: Option<ActionHash>,
+    // Set by the customer at publish time - the delivery proof must then carry a passed ID check
+    #[serde(default)]
+    pub contains_restricted_items: bool,
+    // Required when status is "Cancelled"
+    #[serde(default)]
+    pub cancellation: Option<OrderCancellation>,
+    // Customer confirmed they meet the minimum age - required to publish restricted items
+    #[serde(default)]
+    pub age_confirmed: bool,
+    // Gift flags snapshotted at publish time - the recipient and message stay sealed
+    #[serde(default)]
+    pub is_gift: bool,
+    #[serde(default)]
+    pub hide_prices: bool,
+    // The OrderAssignment once claimed - carried on every later status so validators know the shopper
+    #[serde(default)]
+    pub assignment_hash: Option<ActionHash>,
+    // The customer's Authorized PaymentIntent backing the published order - required at Checkout
+    #[serde(default)]
+    pub payment_intent_hash: Option<ActionHash>,
+    // The DeliveryTimeSlot the order was published for - required at Checkout, carried until the order ends
+    #[serde(default)]
+    pub delivery_time_slot_hash: Option<ActionHash>,
+    // The delivery address (or pickup OrderFulfillment) the order was published with - the delivery proof must be for it
+    #[serde(default)]
+    pub handover_hash: Option<ActionHash>,
+}
+
+// Order state machine - Delivered and Cancelled are final, and a claimed order can no longer be recalled
+pub fn is_allowed_status_transition(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("Shopping", "Shopping")
+            | ("Shopping", "Checkout")
+            | ("Shopping", "Cancelled")
+            | ("Checkout", "Shopping")
+            | ("Checkout", "Claimed")
+            | ("Checkout", "Cancelled")
+            | ("Claimed", "ReadyForPickup")
+            | ("Claimed", "Delivered")
+            | ("Claimed", "Cancelled")
+            | ("ReadyForPickup", "Delivered")
+            | ("ReadyForPickup", "Cancelled")
+    )
+}
+
+pub fn validate_session_status_transition(
+    session_status: &SessionStatus,
+    previous_status: &SessionStatus,
+) -> ExternResult<ValidateCallbackResult> {
+    if !is_allowed_status_transition(&previous_status.status, &session_status.status) {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "An order cannot move from {} to {}",
+            previous_status.status, session_status.status
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Catalog categories whose products must be snapshotted with age_restricted set
+pub const AGE_RESTRICTED_CATEGORIES: [&str; 4] = ["Beer", "Wine", "Liquor", "Hard Beverages"];
+
+impl CartProduct {
+    // Bring a (possibly legacy) snapshot up to the current schema - prices were already converted on read
+    pub fn migrated(mut self) -> Self {
+        self.schema_version = MONEY_SCHEMA_VERSION;
+        self
+    }
+}
+
+// Products from an age-restricted category must carry the flag, so checkout and delivery ask for ID
+pub fn validate_age_restricted_category(cart_product: &CartProduct) -> Option<String> {
+    let restricted_category = cart_product.category.as_deref().is_some_and(|category| {
+        AGE_RESTRICTED_CATEGORIES.iter().any(|restricted| restricted.eq_ignore_ascii_case(category))
+    });
+    if restricted_category && !cart_product.age_restricted {
+        return Some(format!(
+            "{} products must be marked age_restricted",
+            cart_product.category.as_deref().unwrap_or_default()
+        ));
+    }
+    None
+}
+
+// Price fields shared by cart lines and pick substitutes
+pub fn validate_cart_product_prices(cart_product: &CartProduct) -> Option<String> {
+    if cart_product.price_at_checkout.is_negative() {
+        return Some("Cart prices cannot be negative".to_string());
+    }
+    if let Some(promo_price) = &cart_product.promo_price {
+        if promo_price.is_negative() || promo_price.currency != cart_product.price_at_checkout.currency {
+            return Some("Promo price must be non-negative and in the same currency as the price".to_string());
+        }
+    }
+    None
+}
+
+pub fn validate_create_cart_product(
+    action: EntryCreationAction,
+    cart_product: CartProduct,
+) -> ExternResult<ValidateCallbackResult> {
+    if cart_product.schema_version != MONEY_SCHEMA_VERSION {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Cart products must be written with schema version {}",
+            MONEY_SCHEMA_VERSION
+        )));
+    }
+    if let Some(reason) = validate_product_ref(&cart_product.product_id) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if let Some(reason) = validate_cart_product_prices(&cart_product) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if let Some(reason) = validate_age_restricted_category(&cart_product) {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if let Some(reason) = validate_cart_product_quote(&action, &cart_product)? {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+    if let Some(max_quantity) = cart_product.max_quantity {
+        if !max_quantity.is_finite() || max_quantity <= 0.0 {
+            return Ok(ValidateCallbackResult::Invalid(
+                "max_quantity must be a positive number".to_string(),
+            ));
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Quantity links on the public path must stay within the product's limit, and a payment intent
+// can only be attached to a session by the customer who owns it
+pub fn validate_create_cart_data_link(
+    action: CreateLink,
+    target_address: AnyLinkableHash,
+    tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let target_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+    let target_record = must_get_valid_record(target_hash)?;
+    if PaymentIntent::try_from(target_record.clone()).is_ok() && *target_record.action().author() != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer who created a payment intent can link it to their order".to_string(),
+        ));
+    }
+    let cart_product = match CartProduct::try_from(target_record) {
+        Ok(cart_product) => cart_product,
+        // Status, address, slot... links carry no quantity
+        Err(_) => return Ok(ValidateCallbackResult::Valid),
+    };
+
+    if tag.0.len() < 16 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cart product links must carry a quantity tag".to_string(),
+        ));
+    }
+    let (quantity, _) = CartQuantityTag::from_link_tag(&tag);
+    if !quantity.is_finite() || quantity <= 0.0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cart quantities must be positive".to_string(),
+        ));
+    }
+    if let Some(max_quantity) = cart_product.max_quantity {
+        if quantity > max_quantity {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "{} is limited to {} per order",
+                cart_product.product_name, max_quantity
+            )));
+        }
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// The customer of a session is whoever wrote its first status - follow the update chain back to it
+fn session_customer(status_record: Record) -> ExternResult<AgentPubKey> {
+    let mut record = status_record;
+    loop {
+        match record.action() {
+            Action::Update(update) => record = must_get_valid_record(update.original_action_address.clone())?,
+            action => return Ok(action.author().clone()),
+        }
+    }
+}
+
+// A session's first status is written by its customer, starting from Shopping
+pub fn validate_create_session_status(
+    action: EntryCreationAction,
+    session_status: SessionStatus,
+) -> ExternResult<ValidateCallbackResult> {
+    if !is_allowed_status_transition("Shopping", &session_status.status) {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "A new order cannot start in {}",
+            session_status.status
+        )));
+    }
+
+    // A cart cell holds a single order - later statuses must update the first one, so none skip the state machine
+    let activity = must_get_agent_activity(action.author().clone(), ChainFilter::new(action.prev_action().clone()))?;
+    let already_started = activity.iter().any(|item| {
+        matches!(item.action.action(), Action::Create(_))
+            && item.action.action().entry_type() == Some(action.entry_type())
+    });
+    if already_started {
+        return Ok(ValidateCallbackResult::Invalid(
+            "An order has only one SessionStatus - update it instead of creating another".to_string(),
+        ));
+    }
+
+    validate_session_status(action.author(), action.author(), action.timestamp(), None, &session_status)
+}
+
+pub fn validate_update_session_status(
+    action: Update,
+    session_status: SessionStatus,
+) -> ExternResult<ValidateCallbackResult> {
+    let previous_record = must_get_valid_record(action.original_action_address.clone())?;
+    let previous_status = match SessionStatus::try_from(previous_record.clone()) {
+        Ok(entry) => entry,
+        Err(e) => {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Expected to get SessionStatus from Record: {e:?}"
+            )));
+        }
+    };
+    if let ValidateCallbackResult::Invalid(reason) =
+        validate_session_status_transition(&session_status, &previous_status)?
+    {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    let customer = session_customer(previous_record)?;
+    validate_session_status(
+        &action.author,
+        &customer,
+        &action.timestamp,
+        Some((&action.original_action_address, &previous_status)),
+        &session_status,
+    )
+}
+
+// Who may write each status - the customer shops, publishes and recalls, the assigned shopper fulfils.
+// previous is the status being replaced (with its hash), None for a session's first status.
+pub fn validate_session_status(
+    author: &AgentPubKey,
+    customer: &AgentPubKey,
+    timestamp: &Timestamp,
+    previous: Option<(&ActionHash, &SessionStatus)>,
+    session_status: &SessionStatus,
+) -> ExternResult<ValidateCallbackResult> {
+    if let Some((_, previous_status)) = previous {
+        if previous_status.assignment_hash.is_some() && session_status.assignment_hash != previous_status.assignment_hash {
+            return Ok(ValidateCallbackResult::Invalid(
+                "The order's assignment cannot change once it is claimed".to_string(),
+            ));
+        }
+        if previous_status.status != "Shopping"
+            && session_status.status != "Shopping"
+            && (session_status.delivery_time_slot_hash != previous_status.delivery_time_slot_hash
+                || session_status.handover_hash != previous_status.handover_hash)
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "A published order keeps the delivery slot and address it was published for".to_string(),
+            ));
+        }
+        if previous_status.status != "Shopping"
+            && session_status.status != "Shopping"
+            && (session_status.age_confirmed != previous_status.age_confirmed
+                || session_status.is_gift != previous_status.is_gift
+                || session_status.hide_prices != previous_status.hide_prices)
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "A published order keeps its age confirmation and gift flags".to_string(),
+            ));
+        }
+    }
+    let assignment = match &session_status.assignment_hash {
+        Some(assignment_hash) => match OrderAssignment::try_from(must_get_valid_record(assignment_hash.clone())?) {
+            Ok(assignment) if assignment.customer == *customer => Some(assignment),
+            _ => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "assignment_hash must point to an OrderAssignment for this customer's order".to_string(),
+                ));
+            }
+        },
+        None => None,
+    };
+    let is_assigned_shopper = assignment.as_ref().is_some_and(|assignment| assignment.shopper == *author);
+
+    match session_status.status.as_str() {
+        "Shopping" | "Checkout" => {
+            if author != customer {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the customer can shop, publish or recall their order".to_string(),
+                ));
+            }
+            if assignment.is_some() {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "A claimed order cannot be reopened".to_string(),
+                ));
+            }
+        }
+        "Claimed" => {
+            // The shopper claims the exact published status their assignment names
+            let claims_previous = assignment.as_ref().zip(previous).is_some_and(|(assignment, (previous_hash, _))| {
+                assignment.published_status_hash == *previous_hash
+            });
+            if !is_assigned_shopper || !claims_previous {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the shopper whose assignment claimed this published order can mark it Claimed".to_string(),
+                ));
+            }
+        }
+        "ReadyForPickup" | "Delivered" if !is_assigned_shopper => {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Only the assigned shopper can move an order to {}",
+                session_status.status
+            )));
+        }
+        _ => {}
+    }
+
+    // Once published, an order can gain age-restricted lines but never drop the flag (a recall resets it)
+    if let Some((_, previous_status)) = previous {
+        if previous_status.status != "Shopping"
+            && session_status.status != "Shopping"
+            && previous_status.contains_restricted_items
+            && !session_status.contains_restricted_items
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "A published order cannot drop its age-restricted flag".to_string(),
+            ));
+        }
+    }
+
+    if session_status.status == "Cancelled" {
+        return match &session_status.cancellation {
+            Some(cancellation) => validate_order_cancellation(author, customer, timestamp, session_status, cancellation),
+            None => Ok(ValidateCallbackResult::Invalid(
+                "A cancelled order must record who cancelled it and why".to_string(),
+            )),
+        };
+    }
+    if session_status.status == "Checkout" {
+        if session_status.contains_restricted_items && !session_status.age_confirmed {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Orders with age-restricted items need the customer's age confirmation".to_string(),
+            ));
+        }
+        if let Some(reason) = validate_published_payment_intent(customer, &session_status.payment_intent_hash)? {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+        let slot_record = match &session_status.delivery_time_slot_hash {
+            Some(slot_hash) => must_get_valid_record(slot_hash.clone())?,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "A published order must name its delivery slot".to_string(),
+                ));
+            }
+        };
+        if slot_record.action().author() != customer || DeliveryTimeSlot::try_from(slot_record).is_err() {
+            return Ok(ValidateCallbackResult::Invalid(
+                "delivery_time_slot_hash must point to the customer's DeliveryTimeSlot".to_string(),
+            ));
+        }
+        let handover_record = match &session_status.handover_hash {
+            Some(handover_hash) => must_get_valid_record(handover_hash.clone())?,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "A published order must name its delivery address or pickup store".to_string(),
+                ));
+            }
+        };
+        if handover_record.action().author() != customer {
+            return Ok(ValidateCallbackResult::Invalid(
+                "handover_hash must point to the customer's delivery address or pickup choice".to_string(),
+            ));
+        }
+    }
+    if session_status.status != "Delivered" {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+
+    // Delivered requires a proof by the same shopper
+    let proof_hash = match &session_status.delivery_proof_hash {
+        Some(hash) => hash.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "An order can only be marked Delivered with a delivery proof".to_string(),
+            ));
+        }
+    };
+    let proof_record = must_get_valid_record(proof_hash)?;
+    let proof = match DeliveryProof::try_from(proof_record.clone()) {
+        Ok(proof) => proof,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "delivery_proof_hash must point to a DeliveryProof".to_string(),
+            ));
+        }
+    };
+    if session_status.assignment_hash.as_ref() != Some(&proof.assignment_hash) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "The delivery proof must belong to this order's assignment".to_string(),
+        ));
+    }
+    if session_status.contains_restricted_items && proof.age_verification.is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Orders with age-restricted items can only be delivered after an ID check".to_string(),
+        ));
+    }
+    if proof_record.action().author() != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "The Delivered status must be set by the shopper who submitted the proof".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
 }
 
 // Delivery instructions - PUBLIC DHT entry
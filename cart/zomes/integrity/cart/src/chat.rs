@@ -0,0 +1,163 @@
+use hdi::prelude::*;
+
+use crate::{OrderAssignment, SealedEnvelope};
+
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 2000;
+
+// Canned messages the apps can send with one tap
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum QuickReply {
+    ItemOutOfStock,
+    RunningLate,
+    OnMyWay,
+    Arrived,
+    ThankYou,
+}
+
+impl QuickReply {
+    pub fn default_text(&self) -> &'static str {
+        match self {
+            QuickReply::ItemOutOfStock => "Item out of stock",
+            QuickReply::RunningLate => "Running late",
+            QuickReply::OnMyWay => "On my way",
+            QuickReply::Arrived => "I've arrived",
+            QuickReply::ThankYou => "Thank you!",
+        }
+    }
+}
+
+// Plaintext of a chat message - only ever stored sealed inside an OrderMessage
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatMessageContent {
+    pub text: String,
+    pub quick_reply: Option<QuickReply>,
+}
+
+// Chat message between the customer and assigned shopper of an order - PUBLIC DHT entry
+// Linked from the OrderAssignment via AssignmentToMessage; content is sealed for both parties.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct OrderMessage {
+    pub assignment_hash: ActionHash,
+    pub recipient: AgentPubKey,
+    pub sender_key: X25519PubKey,
+    pub envelopes: Vec<SealedEnvelope>,
+    pub sent_at: u64,
+}
+
+pub fn validate_create_order_message(
+    action: EntryCreationAction,
+    message: OrderMessage,
+) -> ExternResult<ValidateCallbackResult> {
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(message.assignment_hash.clone())?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+
+    let author = action.author().clone();
+    let expected_recipient = if author == assignment.customer {
+        assignment.shopper.clone()
+    } else if author == assignment.shopper {
+        assignment.customer.clone()
+    } else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer and shopper of an order can send messages".to_string(),
+        ));
+    };
+    if message.recipient != expected_recipient {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Messages must be addressed to the other party of the order".to_string(),
+        ));
+    }
+
+    if message.envelopes.is_empty()
+        || message
+            .envelopes
+            .iter()
+            .any(|envelope| envelope.recipient != assignment.customer && envelope.recipient != assignment.shopper)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Messages can only be sealed for the two parties of the order".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Chat link (assignment -> message) - the target must be a message of that order, linked by one of its parties
+pub fn validate_create_assignment_message_link(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let message_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Chat links must point to an OrderMessage action".to_string(),
+            ));
+        }
+    };
+    let message = match OrderMessage::try_from(must_get_valid_record(message_hash)?) {
+        Ok(message) => message,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Chat links must point to an OrderMessage".to_string(),
+            ));
+        }
+    };
+    if base_address != AnyLinkableHash::from(message.assignment_hash.clone()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Messages can only be linked from their own order".to_string(),
+        ));
+    }
+
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(message.assignment_hash)?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Chat links must be based on an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if action.author != assignment.customer && action.author != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer and shopper of an order can link its messages".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Read receipt link (message -> reader) may only be created by the message recipient
+pub fn validate_create_message_read_link(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let message_hash = match base_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Read receipts must be based on an OrderMessage action".to_string(),
+            ));
+        }
+    };
+    let message = match OrderMessage::try_from(must_get_valid_record(message_hash)?) {
+        Ok(message) => message,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Read receipts must be based on an OrderMessage".to_string(),
+            ));
+        }
+    };
+    if action.author != message.recipient || target_address != AnyLinkableHash::from(action.author.clone()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the recipient can mark a message as read".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,313 @@
+use hdi::prelude::*;
+
+use crate::{
+    coarsen_coordinate, distance_meters, is_valid_coordinate, location_commitment, Address, DeliveryDataKind,
+    EncryptedDeliveryData, Money, OrderAssignment, OrderFulfillment, PaymentIntent, SessionStatus,
+};
+
+// How far from the delivery address (or pickup store) a proof may be captured
+pub const DELIVERY_PROOF_MAX_DISTANCE_METERS: f64 = 200.0;
+
+// Proof points and revealed address cells are rounded to trail precision (~110m), so validators
+// allow for the rounding of both points on top of the distance limit
+pub const COARSE_LOCATION_TOLERANCE_METERS: f64 = 160.0;
+
+// Photo bytes per chunk entry
+pub const PHOTO_CHUNK_SIZE: usize = 256 * 1024;
+
+// Minimum age checked at the door for orders with age-restricted items
+pub const RESTRICTED_ITEMS_MINIMUM_AGE: u8 = 21;
+
+// Result of the shopper's ID check at handover - no document details are stored
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AgeVerification {
+    pub document_type: String, // e.g. "drivers_license", "passport"
+    pub minimum_age: u8,
+    pub recipient_meets_minimum_age: bool,
+    pub checked_at: u64,
+}
+
+// The sealed address's trail-precision cell and salt, revealed by the shopper so validators can run the
+// geofence - the exact coordinates stay sealed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AddressLocationOpening {
+    pub lat: f64,
+    pub lng: f64,
+    pub salt: Vec<u8>,
+}
+
+// One chunk of a delivery photo stored on the DHT - PUBLIC DHT entry
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct PhotoChunk {
+    pub chunk_index: u32,
+    pub bytes: Vec<u8>,
+}
+
+// Shopper's proof that an order was handed over - PUBLIC DHT entry
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct DeliveryProof {
+    pub assignment_hash: ActionHash,
+    // The Address (legacy plaintext) or sealed EncryptedDeliveryData record delivered to,
+    // or the OrderFulfillment naming the store for pickup orders
+    pub address_hash: ActionHash,
+    // blake2b-256 of the full photo bytes; the photo lives in PhotoChunk entries or at photo_url
+    pub photo_content_hash: Vec<u8>,
+    pub photo_chunks: Vec<ActionHash>,
+    pub photo_url: Option<String>,
+    // Where the photo was taken, rounded to trail precision
+    pub lat: f64,
+    pub lng: f64,
+    pub delivered_at: u64,
+    pub recipient_name: Option<String>,
+    // Shopper's signature over the raw assignment (order) hash
+    pub order_signature: Signature,
+    // Mandatory when the published order contains age-restricted items
+    #[serde(default)]
+    pub age_verification: Option<AgeVerification>,
+    // Shopper's own check of the cart lines at handover - an ID check is required when set
+    #[serde(default)]
+    pub contains_restricted_items: bool,
+    // What the shopper's reconciliation of the picks comes to - the amount the customer captures
+    #[serde(default)]
+    pub delivered_total: Option<Money>,
+    // Mandatory when address_hash points to a sealed address
+    #[serde(default)]
+    pub address_opening: Option<AddressLocationOpening>,
+}
+
+pub fn validate_create_photo_chunk(
+    _action: EntryCreationAction,
+    chunk: PhotoChunk,
+) -> ExternResult<ValidateCallbackResult> {
+    if chunk.bytes.is_empty() || chunk.bytes.len() > PHOTO_CHUNK_SIZE {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Photo chunks must hold between 1 and {} bytes",
+            PHOTO_CHUNK_SIZE
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_create_delivery_proof(
+    action: EntryCreationAction,
+    proof: DeliveryProof,
+) -> ExternResult<ValidateCallbackResult> {
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(proof.assignment_hash.clone())?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if *action.author() != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the assigned shopper can submit a delivery proof".to_string(),
+        ));
+    }
+
+    let signed = verify_signature_raw(
+        action.author().clone(),
+        proof.order_signature.clone(),
+        proof.assignment_hash.get_raw_39().to_vec(),
+    )?;
+    if !signed {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof signature does not match the order hash".to_string(),
+        ));
+    }
+
+    let published_status = match SessionStatus::try_from(must_get_valid_record(assignment.published_status_hash.clone())?) {
+        Ok(status) => status,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "The order's published status must be a SessionStatus".to_string(),
+            ));
+        }
+    };
+    match &proof.age_verification {
+        Some(check) => {
+            if check.document_type.trim().is_empty() || check.minimum_age < RESTRICTED_ITEMS_MINIMUM_AGE {
+                return Ok(ValidateCallbackResult::Invalid(format!(
+                    "ID checks must name the document and check for age {} or over",
+                    RESTRICTED_ITEMS_MINIMUM_AGE
+                )));
+            }
+            if !check.recipient_meets_minimum_age {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Age-restricted orders cannot be delivered to a recipient who failed the ID check".to_string(),
+                ));
+            }
+        }
+        None if published_status.contains_restricted_items || proof.contains_restricted_items => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Orders with age-restricted items require an ID check before delivery".to_string(),
+            ));
+        }
+        None => {}
+    }
+
+    // The delivered total is what gets captured - within the authorization the order was published with
+    let payment_intent = match &published_status.payment_intent_hash {
+        Some(hash) => PaymentIntent::try_from(must_get_valid_record(hash.clone())?).ok(),
+        None => None,
+    };
+    match (&proof.delivered_total, payment_intent) {
+        (Some(total), Some(payment_intent)) => {
+            if total.is_negative()
+                || total.currency != payment_intent.currency
+                || total.amount_minor > payment_intent.authorized_amount.amount_minor
+            {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "The delivered total must be within the order's authorized amount".to_string(),
+                ));
+            }
+        }
+        (Some(_), None) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "The order has no authorized payment to deliver a total against".to_string(),
+            ));
+        }
+        (None, Some(_)) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Delivery proofs for paid orders must state the delivered total".to_string(),
+            ));
+        }
+        (None, None) => {}
+    }
+
+    if !is_valid_coordinate(proof.lat, proof.lng) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof coordinates are out of range".to_string(),
+        ));
+    }
+    if !is_coarse_point(proof.lat, proof.lng) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof coordinates must be rounded to trail precision".to_string(),
+        ));
+    }
+
+    if proof.photo_content_hash.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof requires a photo hash".to_string(),
+        ));
+    }
+    if proof.photo_chunks.is_empty() && proof.photo_url.is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proof photo must be stored in chunks or at an external URL".to_string(),
+        ));
+    }
+    if let Some(reason) = validate_photo_chunks(action.author(), &proof)? {
+        return Ok(ValidateCallbackResult::Invalid(reason));
+    }
+
+    // The handover point is the one the order was published with - the sealed address may since
+    // have been re-sealed for the shopper, which updates it in place
+    let published_handover = match &published_status.handover_hash {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "The published order does not name its delivery address or pickup store".to_string(),
+            ));
+        }
+    };
+    if !is_same_or_update_of(proof.address_hash.clone(), published_handover)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "address_hash must point to the delivery address or pickup store the order was published with".to_string(),
+        ));
+    }
+
+    // Plaintext addresses and pickup stores are read directly; sealed addresses are checked
+    // against the opening the shopper revealed, which must match the sealed location commitment
+    let address_record = must_get_valid_record(proof.address_hash.clone())?;
+    if *address_record.action().author() != assignment.customer {
+        return Ok(ValidateCallbackResult::Invalid(
+            "address_hash must point to the customer's delivery address".to_string(),
+        ));
+    }
+    let (lat, lng) = if let Ok(address) = Address::try_from(address_record.clone()) {
+        (address.lat, address.lng)
+    } else if let Ok(sealed) = EncryptedDeliveryData::try_from(address_record.clone()) {
+        let (opening, commitment) = match (&proof.address_opening, &sealed.location_commitment) {
+            (Some(opening), Some(commitment)) if sealed.kind == DeliveryDataKind::Address => (opening, commitment),
+            _ => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Deliveries to a sealed address must reveal its committed location".to_string(),
+                ));
+            }
+        };
+        if !is_coarse_point(opening.lat, opening.lng)
+            || location_commitment(opening.lat, opening.lng, &opening.salt)? != *commitment
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "address_opening does not match the sealed address".to_string(),
+            ));
+        }
+        (opening.lat, opening.lng)
+    } else {
+        match OrderFulfillment::try_from(address_record).ok().and_then(|fulfillment| fulfillment.store) {
+            Some(store) => (store.lat, store.lng),
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "address_hash must point to a delivery address or a pickup store".to_string(),
+                ));
+            }
+        }
+    };
+    let distance = distance_meters(proof.lat, proof.lng, lat, lng);
+    if distance > DELIVERY_PROOF_MAX_DISTANCE_METERS + COARSE_LOCATION_TOLERANCE_METERS {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Delivery proof was captured {:.0}m from the handover point (limit {:.0}m)",
+            distance, DELIVERY_PROOF_MAX_DISTANCE_METERS
+        )));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+fn is_coarse_point(lat: f64, lng: f64) -> bool {
+    (coarsen_coordinate(lat) - lat).abs() < 1e-9 && (coarsen_coordinate(lng) - lng).abs() < 1e-9
+}
+
+// Whether `hash` is `root` or reached from it through in-place updates
+fn is_same_or_update_of(mut hash: ActionHash, root: &ActionHash) -> ExternResult<bool> {
+    loop {
+        if hash == *root {
+            return Ok(true);
+        }
+        match must_get_action(hash)?.action() {
+            Action::Update(update) => hash = update.original_action_address.clone(),
+            _ => return Ok(false),
+        }
+    }
+}
+
+// Chunks must be the shopper's own, numbered 0..n, and hash to photo_content_hash when put back together
+fn validate_photo_chunks(author: &AgentPubKey, proof: &DeliveryProof) -> ExternResult<Option<String>> {
+    let mut chunks = Vec::with_capacity(proof.photo_chunks.len());
+    for chunk_hash in &proof.photo_chunks {
+        let record = must_get_valid_record(chunk_hash.clone())?;
+        if record.action().author() != author {
+            return Ok(Some("Delivery photo chunks must be uploaded by the shopper".to_string()));
+        }
+        match PhotoChunk::try_from(record) {
+            Ok(chunk) => chunks.push(chunk),
+            Err(_) => return Ok(Some("photo_chunks must point to PhotoChunk entries".to_string())),
+        }
+    }
+    if chunks.is_empty() {
+        return Ok(None);
+    }
+
+    chunks.sort_by_key(|chunk| chunk.chunk_index);
+    if chunks.iter().enumerate().any(|(position, chunk)| chunk.chunk_index as usize != position) {
+        return Ok(Some("Delivery photo chunks must be numbered from 0 without gaps".to_string()));
+    }
+    let photo: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.bytes).collect();
+    if hash_blake2b(photo, 32)? != proof.photo_content_hash {
+        return Ok(Some("Delivery photo chunks do not match photo_content_hash".to_string()));
+    }
+    Ok(None)
+}
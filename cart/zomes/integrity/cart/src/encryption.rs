@@ -0,0 +1,107 @@
+use hdi::prelude::*;
+
+use crate::{OrderAssignment, LOCATION_COMMITMENT_LENGTH};
+
+// Agent's published X25519 public key (private half stays in the keystore) - PUBLIC DHT entry
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct AgentEncryptionKey {
+    pub x25519_pub_key: X25519PubKey,
+    pub created_at: u64,
+}
+
+// Which delivery detail a sealed entry carries
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DeliveryDataKind {
+    Address,      // msgpack-encoded Address
+    Instructions, // msgpack-encoded DeliveryInstructions
+    Gift,         // msgpack-encoded GiftDetails
+}
+
+// The same plaintext boxed for a single recipient
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SealedEnvelope {
+    pub recipient: AgentPubKey,
+    pub recipient_key: X25519PubKey,
+    pub sealed: XSalsa20Poly1305EncryptedData,
+}
+
+// Delivery address, instructions or gift details encrypted to the customer and (once claimed) the assigned shopper
+// PUBLIC DHT entry, but only envelope recipients can open it
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct EncryptedDeliveryData {
+    pub kind: DeliveryDataKind,
+    pub sender_key: X25519PubKey,
+    // Required as soon as anyone other than the author is a recipient
+    pub assignment_hash: Option<ActionHash>,
+    pub envelopes: Vec<SealedEnvelope>,
+    pub sealed_at: u64,
+    // Address kind only - location_commitment over the sealed lat/lng and the address's location_salt
+    #[serde(default)]
+    pub location_commitment: Option<Vec<u8>>,
+}
+
+pub fn validate_create_encrypted_delivery_data(
+    action: EntryCreationAction,
+    data: EncryptedDeliveryData,
+) -> ExternResult<ValidateCallbackResult> {
+    let author = action.author().clone();
+
+    let commitment_ok = match (&data.kind, &data.location_commitment) {
+        (DeliveryDataKind::Address, Some(commitment)) => commitment.len() == LOCATION_COMMITMENT_LENGTH as usize,
+        (DeliveryDataKind::Address, None) => false,
+        (_, commitment) => commitment.is_none(),
+    };
+    if !commitment_ok {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Sealed addresses need a location commitment, and only addresses can carry one".to_string(),
+        ));
+    }
+
+    if !data.envelopes.iter().any(|envelope| envelope.recipient == author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery data must always be readable by its author".to_string(),
+        ));
+    }
+
+    let other_recipients: Vec<&AgentPubKey> = data
+        .envelopes
+        .iter()
+        .map(|envelope| &envelope.recipient)
+        .filter(|recipient| **recipient != author)
+        .collect();
+    if other_recipients.is_empty() {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+
+    // Anyone else must be the assigned shopper of an order this author published
+    let assignment_hash = match &data.assignment_hash {
+        Some(hash) => hash.clone(),
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Delivery data can only be shared after the order is claimed".to_string(),
+            ));
+        }
+    };
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(assignment_hash)?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if assignment.customer != author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer can share their delivery data".to_string(),
+        ));
+    }
+    if other_recipients.iter().any(|recipient| **recipient != assignment.shopper) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery data can only be shared with the assigned shopper".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,56 @@
+use hdi::prelude::*;
+
+use crate::is_valid_coordinate;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum FulfillmentMode {
+    #[default]
+    Delivery,
+    Pickup,
+}
+
+// A store that offers click-and-collect
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoreLocation {
+    pub store_id: String,
+    pub name: String,
+    pub address: String, // Display address, e.g. "12 Market St, Springfield"
+    pub lat: f64,
+    pub lng: f64,
+}
+
+// How the customer receives the order - PUBLIC DHT entry linked from the cart path like the time slot.
+// Sessions without one are delivered; for pickup the DeliveryTimeSlot is the pickup slot.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct OrderFulfillment {
+    pub mode: FulfillmentMode,
+    pub store: Option<StoreLocation>, // Required for Pickup
+}
+
+pub fn validate_create_order_fulfillment(
+    _action: EntryCreationAction,
+    fulfillment: OrderFulfillment,
+) -> ExternResult<ValidateCallbackResult> {
+    match (&fulfillment.mode, &fulfillment.store) {
+        (FulfillmentMode::Pickup, Some(store)) => {
+            if store.store_id.trim().is_empty() || !is_valid_coordinate(store.lat, store.lng) {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Pickup stores need an id and valid coordinates".to_string(),
+                ));
+            }
+        }
+        (FulfillmentMode::Pickup, None) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Pickup orders must name a store".to_string(),
+            ));
+        }
+        (FulfillmentMode::Delivery, Some(_)) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Delivery orders cannot name a pickup store".to_string(),
+            ));
+        }
+        (FulfillmentMode::Delivery, None) => {}
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,31 @@
+// Shared geographic helpers for delivery validation and routing
+
+use hdi::prelude::*;
+
+pub const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+// Great-circle distance between two lat/lng points in meters (haversine)
+pub fn distance_meters(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lng = (lng2 - lng1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+// Basic sanity check for coordinates coming from a device
+pub fn is_valid_coordinate(lat: f64, lng: f64) -> bool {
+    lat.is_finite() && lng.is_finite() && (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng)
+}
+
+// Length of a salted location commitment in bytes
+pub const LOCATION_COMMITMENT_LENGTH: u8 = 32;
+
+// blake2b-256 over lat, lng and salt - lets validators check a revealed location against a sealed address
+pub fn location_commitment(lat: f64, lng: f64, salt: &[u8]) -> ExternResult<Vec<u8>> {
+    let mut input = Vec::with_capacity(16 + salt.len());
+    input.extend_from_slice(&lat.to_le_bytes());
+    input.extend_from_slice(&lng.to_le_bytes());
+    input.extend_from_slice(salt);
+    hash_blake2b(input, LOCATION_COMMITMENT_LENGTH)
+}
@@ -0,0 +1,30 @@
+use hdi::prelude::*;
+
+pub const MAX_GIFT_MESSAGE_LENGTH: usize = 500;
+
+// Who a gift order goes to and what the card says - sealed into EncryptedDeliveryData (kind Gift)
+// like the address, so only the customer and the assigned shopper can read it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GiftDetails {
+    pub recipient_name: String,
+    pub recipient_phone: String,
+    pub gift_message: Option<String>,
+    pub hide_prices: bool, // Leave prices off the packing slip
+}
+
+// Sealed content is invisible to validators, so the customer's coordinator checks it before sealing
+pub fn validate_gift_details(details: &GiftDetails) -> Option<String> {
+    if details.recipient_name.trim().is_empty() {
+        return Some("Gift orders need a recipient name".to_string());
+    }
+    let digits = details.recipient_phone.chars().filter(|c| c.is_ascii_digit()).count();
+    if !(7..=15).contains(&digits) {
+        return Some("Gift recipient phone numbers need 7 to 15 digits".to_string());
+    }
+    if let Some(message) = &details.gift_message {
+        if message.chars().count() > MAX_GIFT_MESSAGE_LENGTH {
+            return Some(format!("Gift messages cannot exceed {} characters", MAX_GIFT_MESSAGE_LENGTH));
+        }
+    }
+    None
+}
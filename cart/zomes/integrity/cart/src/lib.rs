@@ -1,11 +1,75 @@
 use hdi::prelude::*;
 
+// Money, ProductRef and the price quote types are shared with products_integrity
+pub use catalog::*;
+pub use money::*;
+
 mod cart;
 pub use cart::*;
 
 mod address;
 pub use address::*;
 
+mod order;
+pub use order::*;
+
+mod rating;
+pub use rating::*;
+
+mod picking;
+pub use picking::*;
+
+mod payment;
+pub use payment::*;
+
+mod encryption;
+pub use encryption::*;
+
+mod chat;
+pub use chat::*;
+
+mod geo;
+pub use geo::*;
+
+mod delivery_proof;
+pub use delivery_proof::*;
+
+mod location;
+pub use location::*;
+
+mod recurring;
+pub use recurring::*;
+
+mod shopping_list;
+pub use shopping_list::*;
+
+mod fulfillment;
+pub use fulfillment::*;
+
+mod gift;
+pub use gift::*;
+
+mod batch;
+pub use batch::*;
+
+mod barcode;
+pub use barcode::*;
+
+mod price_quote;
+pub use price_quote::*;
+
+mod product_ref;
+pub use product_ref::*;
+
+mod offline_queue;
+pub use offline_queue::*;
+
+mod notification;
+pub use notification::*;
+
+mod properties;
+pub use properties::*;
+
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[hdk_entry_types]
@@ -16,6 +80,33 @@ pub enum EntryTypes {
     Address(Address),
     DeliveryTimeSlot(DeliveryTimeSlot),
     DeliveryInstructions(DeliveryInstructions),
+    OrderAssignment(OrderAssignment),
+    Rating(Rating),
+    PickRecord(PickRecord),
+    PaymentIntent(PaymentIntent),
+    AgentEncryptionKey(AgentEncryptionKey),
+    EncryptedDeliveryData(EncryptedDeliveryData),
+    OrderMessage(OrderMessage),
+    PhotoChunk(PhotoChunk),
+    DeliveryProof(DeliveryProof),
+    LocationTrailPoint(LocationTrailPoint),
+    #[entry_type(visibility = "private")]
+    RecurringOrder(RecurringOrder),
+    ShoppingList(ShoppingList),
+    ShoppingListItem(ShoppingListItem),
+    OrderFulfillment(OrderFulfillment),
+    #[entry_type(visibility = "private")]
+    ShopperBatch(ShopperBatch),
+    #[entry_type(visibility = "private")]
+    QueuedCartOp(QueuedCartOp),
+    #[entry_type(visibility = "private")]
+    CartOpReceipt(CartOpReceipt),
+    #[entry_type(visibility = "private")]
+    Notification(Notification),
+    #[entry_type(visibility = "private")]
+    NotificationRead(NotificationRead),
+    #[entry_type(visibility = "private")]
+    NotificationPreferences(NotificationPreferences),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -23,6 +114,22 @@ pub enum EntryTypes {
 pub enum LinkTypes {
     // Single LinkType for all public cart data - everything uses same path
     PublicPathToCartData,
+    // Ratee agent to the ratings they received
+    AgentToRating,
+    // Cart product to the shopper's pick records for that line (latest wins)
+    CartProductToPick,
+    // Agent to their published X25519 public key
+    AgentToEncryptionKey,
+    // Order assignment to its chat messages
+    AssignmentToMessage,
+    // Chat message to the agent who read it (tag holds the read timestamp)
+    MessageToReader,
+    // Order assignment to the shopper's coarse location trail
+    AssignmentToTrailPoint,
+    // Shopping list (first version) to the current version of each of its items
+    ShoppingListToItem,
+    // Owner or member to the shopping lists they can see
+    AgentToShoppingList,
 }
 
 // Genesis validation
@@ -44,19 +151,88 @@ pub fn validate_agent_joining(
 pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
     match op.flattened::<EntryTypes, LinkTypes>()? {
         FlatOp::StoreEntry(store_entry) => match store_entry {
-            OpEntry::CreateEntry { .. } => Ok(ValidateCallbackResult::Valid),
-            OpEntry::UpdateEntry { .. } => Ok(ValidateCallbackResult::Valid),
+            OpEntry::CreateEntry { app_entry, action } => {
+                validate_create_entry(EntryCreationAction::Create(action), app_entry)
+            }
+            OpEntry::UpdateEntry { app_entry, action, .. } => {
+                validate_update_entry(action, app_entry)
+            }
             _ => Ok(ValidateCallbackResult::Valid),
         },
         FlatOp::RegisterUpdate(_) => Ok(ValidateCallbackResult::Valid),
-        FlatOp::RegisterDelete(_) => Ok(ValidateCallbackResult::Valid),
-        FlatOp::RegisterCreateLink { link_type, .. } => match link_type {
-            LinkTypes::PublicPathToCartData => Ok(ValidateCallbackResult::Valid),
+        FlatOp::RegisterDelete(delete_entry) => {
+            validate_delete_entry(delete_entry.action.deletes_address)
+        }
+        FlatOp::RegisterCreateLink {
+            link_type,
+            base_address,
+            target_address,
+            tag,
+            action,
+            ..
+        } => match link_type {
+            LinkTypes::PublicPathToCartData => validate_create_cart_data_link(action, target_address, tag),
+            LinkTypes::AgentToRating => validate_create_rating_link(action, base_address, target_address),
+            LinkTypes::CartProductToPick => validate_create_pick_link(action, base_address, target_address),
+            LinkTypes::AgentToEncryptionKey => {
+                if base_address != AnyLinkableHash::from(action.author) {
+                    return Ok(ValidateCallbackResult::Invalid(
+                        "Agents can only publish their own encryption key".to_string(),
+                    ));
+                }
+                Ok(ValidateCallbackResult::Valid)
+            }
+            LinkTypes::AssignmentToMessage => {
+                validate_create_assignment_message_link(action, base_address, target_address)
+            }
+            LinkTypes::MessageToReader => {
+                validate_create_message_read_link(action, base_address, target_address)
+            }
+            LinkTypes::AssignmentToTrailPoint => {
+                validate_create_trail_point_link(action, base_address, target_address)
+            }
+            LinkTypes::ShoppingListToItem => {
+                validate_create_shopping_list_item_link(action, base_address, target_address)
+            }
+            LinkTypes::AgentToShoppingList => {
+                validate_create_agent_to_shopping_list_link(action, target_address)
+            }
         },
-        FlatOp::RegisterDeleteLink { link_type, .. } => match link_type {
+        FlatOp::RegisterDeleteLink {
+            link_type,
+            target_address,
+            action,
+            ..
+        } => match link_type {
             LinkTypes::PublicPathToCartData => Ok(ValidateCallbackResult::Valid),
+            LinkTypes::AgentToRating => Ok(ValidateCallbackResult::Invalid(
+                "Rating links cannot be deleted".to_string(),
+            )),
+            LinkTypes::CartProductToPick => Ok(ValidateCallbackResult::Invalid(
+                "Picks cannot be unlinked - record a new pick instead".to_string(),
+            )),
+            LinkTypes::AgentToEncryptionKey => Ok(ValidateCallbackResult::Valid),
+            LinkTypes::AssignmentToMessage => Ok(ValidateCallbackResult::Invalid(
+                "Chat messages cannot be unlinked from their order".to_string(),
+            )),
+            LinkTypes::MessageToReader => Ok(ValidateCallbackResult::Invalid(
+                "Read receipts cannot be deleted".to_string(),
+            )),
+            LinkTypes::AssignmentToTrailPoint => Ok(ValidateCallbackResult::Invalid(
+                "Location trail points cannot be unlinked".to_string(),
+            )),
+            LinkTypes::ShoppingListToItem => validate_delete_shopping_list_item_link(action, target_address),
+            LinkTypes::AgentToShoppingList => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::StoreRecord(store_record) => match store_record {
+            OpRecord::CreateEntry { app_entry, action } => {
+                validate_create_entry(EntryCreationAction::Create(action), app_entry)
+            }
+            OpRecord::UpdateEntry { app_entry, action, .. } => {
+                validate_update_entry(action, app_entry)
+            }
+            _ => Ok(ValidateCallbackResult::Valid),
         },
-        FlatOp::StoreRecord(_) => Ok(ValidateCallbackResult::Valid),
         FlatOp::RegisterAgentActivity(agent_activity) => match agent_activity {
             OpActivity::CreateAgent { agent, action } => {
                 let previous_action = must_get_action(action.prev_action)?;
@@ -73,4 +249,159 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
             _ => Ok(ValidateCallbackResult::Valid),
         },
     }
-}
\ No newline at end of file
+}
+
+// Entry-specific create validation - entry types without rules are always valid
+fn validate_create_entry(
+    action: EntryCreationAction,
+    app_entry: EntryTypes,
+) -> ExternResult<ValidateCallbackResult> {
+    match app_entry {
+        EntryTypes::OrderAssignment(assignment) => {
+            validate_create_order_assignment(action, assignment)
+        }
+        EntryTypes::CartProduct(cart_product) => validate_create_cart_product(action, cart_product),
+        EntryTypes::Rating(rating) => validate_create_rating(action, rating),
+        EntryTypes::RecurringOrder(recurring_order) => validate_create_recurring_order(action, recurring_order),
+        EntryTypes::PickRecord(pick_record) => validate_create_pick_record(action, pick_record),
+        EntryTypes::PaymentIntent(payment_intent) => {
+            validate_create_payment_intent(action, payment_intent)
+        }
+        EntryTypes::EncryptedDeliveryData(data) => {
+            validate_create_encrypted_delivery_data(action, data)
+        }
+        EntryTypes::OrderMessage(message) => validate_create_order_message(action, message),
+        EntryTypes::PhotoChunk(chunk) => validate_create_photo_chunk(action, chunk),
+        EntryTypes::DeliveryProof(proof) => validate_create_delivery_proof(action, proof),
+        EntryTypes::LocationTrailPoint(point) => validate_create_location_trail_point(action, point),
+        EntryTypes::ShoppingList(shopping_list) => validate_create_shopping_list(action, shopping_list),
+        EntryTypes::ShoppingListItem(item) => validate_create_shopping_list_item(action, item),
+        EntryTypes::OrderFulfillment(fulfillment) => validate_create_order_fulfillment(action, fulfillment),
+        EntryTypes::ShopperBatch(batch) => validate_create_shopper_batch(action, batch),
+        EntryTypes::QueuedCartOp(queued_op) => validate_create_queued_cart_op(action, queued_op),
+        EntryTypes::Notification(notification) => validate_create_notification(action, notification),
+        EntryTypes::SessionStatus(session_status) => validate_create_session_status(action, session_status),
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+// Entry-specific update validation - session entries are updated in place, ratings are final
+fn validate_update_entry(
+    action: Update,
+    app_entry: EntryTypes,
+) -> ExternResult<ValidateCallbackResult> {
+    match app_entry {
+        EntryTypes::PaymentIntent(payment_intent) => {
+            let original_record = must_get_valid_record(action.original_action_address.clone())?;
+            if *original_record.action().author() != action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the customer who created a payment intent can move it through its lifecycle".to_string(),
+                ));
+            }
+            let original_payment_intent = match PaymentIntent::try_from(original_record) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    return Ok(ValidateCallbackResult::Invalid(format!(
+                        "Expected to get PaymentIntent from Record: {e:?}"
+                    )));
+                }
+            };
+            validate_update_payment_intent(action, payment_intent, original_payment_intent)
+        }
+        EntryTypes::EncryptedDeliveryData(data) => {
+            let original_record = must_get_valid_record(action.original_action_address.clone())?;
+            if *original_record.action().author() != action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the original author can re-seal delivery data".to_string(),
+                ));
+            }
+            validate_create_encrypted_delivery_data(EntryCreationAction::Update(action), data)
+        }
+        EntryTypes::SessionStatus(session_status) => validate_update_session_status(action, session_status),
+        EntryTypes::ShoppingList(shopping_list) => {
+            let original_record = must_get_valid_record(action.original_action_address.clone())?;
+            let original_shopping_list = match ShoppingList::try_from(original_record) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    return Ok(ValidateCallbackResult::Invalid(format!(
+                        "Expected to get ShoppingList from Record: {e:?}"
+                    )));
+                }
+            };
+            validate_update_shopping_list(action, shopping_list, original_shopping_list)
+        }
+        EntryTypes::ShoppingListItem(item) => {
+            let original_record = must_get_valid_record(action.original_action_address.clone())?;
+            let original_item = match ShoppingListItem::try_from(original_record) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    return Ok(ValidateCallbackResult::Invalid(format!(
+                        "Expected to get ShoppingListItem from Record: {e:?}"
+                    )));
+                }
+            };
+            validate_update_shopping_list_item(action, item, original_item)
+        }
+        EntryTypes::RecurringOrder(recurring_order) => {
+            // Private entry - only the original action is visible, which is enough to check authorship
+            let original_action = must_get_action(action.original_action_address.clone())?;
+            if *original_action.action().author() != action.author {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Only the customer who created a recurring order can change it".to_string(),
+                ));
+            }
+            validate_create_recurring_order(EntryCreationAction::Update(action), recurring_order)
+        }
+        EntryTypes::DeliveryProof(_) | EntryTypes::PhotoChunk(_) => Ok(ValidateCallbackResult::Invalid(
+            "Delivery proofs cannot be modified".to_string(),
+        )),
+        EntryTypes::LocationTrailPoint(_) => Ok(ValidateCallbackResult::Invalid(
+            "Location trail points cannot be modified".to_string(),
+        )),
+        EntryTypes::OrderMessage(_) => Ok(ValidateCallbackResult::Invalid(
+            "Chat messages cannot be edited".to_string(),
+        )),
+        EntryTypes::PickRecord(_) => Ok(ValidateCallbackResult::Invalid(
+            "Pick records cannot be updated - record a new pick instead".to_string(),
+        )),
+        EntryTypes::OrderAssignment(_) => Ok(ValidateCallbackResult::Invalid(
+            "Order assignments cannot be updated".to_string(),
+        )),
+        EntryTypes::Rating(_) => Ok(ValidateCallbackResult::Invalid(
+            "Ratings cannot be updated".to_string(),
+        )),
+        EntryTypes::ShopperBatch(_) => Ok(ValidateCallbackResult::Invalid(
+            "Batches cannot be changed - create a new one".to_string(),
+        )),
+        EntryTypes::QueuedCartOp(_) | EntryTypes::CartOpReceipt(_) => Ok(ValidateCallbackResult::Invalid(
+            "Queued cart ops and their receipts cannot be changed".to_string(),
+        )),
+        EntryTypes::Notification(_) | EntryTypes::NotificationRead(_) | EntryTypes::NotificationPreferences(_) => {
+            Ok(ValidateCallbackResult::Invalid(
+                "Notifications are not updated - write a new entry instead".to_string(),
+            ))
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+// Entry-specific delete validation based on the original entry being deleted
+fn validate_delete_entry(original_action_hash: ActionHash) -> ExternResult<ValidateCallbackResult> {
+    let original_record = must_get_valid_record(original_action_hash)?;
+    if Rating::try_from(original_record.clone()).is_ok() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Ratings cannot be deleted".to_string(),
+        ));
+    }
+    if DeliveryProof::try_from(original_record.clone()).is_ok() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Delivery proofs cannot be deleted".to_string(),
+        ));
+    }
+    if OrderAssignment::try_from(original_record).is_ok() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Order assignments cannot be deleted".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
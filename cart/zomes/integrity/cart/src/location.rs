@@ -0,0 +1,121 @@
+use hdi::prelude::*;
+
+use crate::{is_valid_coordinate, OrderAssignment};
+
+// Trail points are rounded to 3 decimals (~110m) before they are persisted
+pub const TRAIL_COORDINATE_DECIMALS: i32 = 3;
+
+// Round a coordinate to the nearest trail-precision step
+pub fn coarsen_coordinate(value: f64) -> f64 {
+    let factor = 10f64.powi(TRAIL_COORDINATE_DECIMALS);
+    (value * factor).round() / factor
+}
+
+// Coarse-grained shopper position kept for dispute resolution - PUBLIC DHT entry
+// Live positions are never persisted; they travel as remote signals only.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct LocationTrailPoint {
+    pub assignment_hash: ActionHash,
+    pub lat: f64,
+    pub lng: f64,
+    pub recorded_at: u64,
+}
+
+pub fn validate_create_location_trail_point(
+    action: EntryCreationAction,
+    point: LocationTrailPoint,
+) -> ExternResult<ValidateCallbackResult> {
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(point.assignment_hash.clone())?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if *action.author() != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the assigned shopper can record a location trail".to_string(),
+        ));
+    }
+    if !is_valid_coordinate(point.lat, point.lng) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Trail coordinates are out of range".to_string(),
+        ));
+    }
+    if (coarsen_coordinate(point.lat) - point.lat).abs() > 1e-9
+        || (coarsen_coordinate(point.lng) - point.lng).abs() > 1e-9
+    {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Trail coordinates must be rounded to {} decimals",
+            TRAIL_COORDINATE_DECIMALS
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Trail links (assignment -> point) may only be created by the assigned shopper, from the point's own order
+pub fn validate_create_trail_point_link(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let point_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Trail links must point to a LocationTrailPoint action".to_string(),
+            ));
+        }
+    };
+    let point = match LocationTrailPoint::try_from(must_get_valid_record(point_hash)?) {
+        Ok(point) => point,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Trail links must point to a LocationTrailPoint".to_string(),
+            ));
+        }
+    };
+    if base_address != AnyLinkableHash::from(point.assignment_hash.clone()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Trail points can only be linked from their own order".to_string(),
+        ));
+    }
+
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(point.assignment_hash)?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Trail links must be based on an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if action.author != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the assigned shopper can link trail points".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coarsen_coordinate_rounds_to_trail_precision() {
+        assert_eq!(coarsen_coordinate(51.50735), 51.507);
+        assert_eq!(coarsen_coordinate(51.50751), 51.508);
+        assert_eq!(coarsen_coordinate(-0.12776), -0.128);
+        assert_eq!(coarsen_coordinate(0.0), 0.0);
+    }
+
+    #[test]
+    fn coarsen_coordinate_is_stable() {
+        for value in [51.50735, -0.12776, 179.9996, -89.00049] {
+            let coarse = coarsen_coordinate(value);
+            assert_eq!(coarsen_coordinate(coarse), coarse);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use hdi::prelude::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    OrderClaimed,
+    SubstitutionProposed,
+    ShopperEnRoute,
+    OrderDelivered,
+    SlotCutoffApproaching,
+    RecurringOrderAwaitingConfirmation,
+}
+
+// Something the agent should know about an order - PRIVATE entry on the recipient's own chain.
+// Each cart session is its own cell, so an order's notifications live in that order's cell.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    // Same event delivered twice (signal retry, repeated cut-off check) is only stored once
+    pub dedupe_key: String,
+    // Entry the notification is about - assignment, pick record, delivery proof, recurring order...
+    pub subject_hash: Option<ActionHash>,
+    pub from: Option<AgentPubKey>,
+    pub created_at: u64,
+}
+
+// Marks a notification read - PRIVATE entry
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct NotificationRead {
+    pub notification_hash: ActionHash,
+    pub read_at: u64,
+}
+
+// Kinds the agent does not want stored or signalled - PRIVATE entry, the latest one wins
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct NotificationPreferences {
+    pub muted: Vec<NotificationKind>,
+    pub updated_at: u64,
+}
+
+pub fn validate_create_notification(
+    _action: EntryCreationAction,
+    notification: Notification,
+) -> ExternResult<ValidateCallbackResult> {
+    if notification.title.trim().is_empty() || notification.dedupe_key.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Notifications need a title and a dedupe key".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,67 @@
+use hdi::prelude::*;
+
+use crate::CartProduct;
+
+// A cart change made while offline - replayed against the live cart when the network is back
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum CartOp {
+    AddItem { product: Box<CartProduct>, quantity: f64 },
+    RemoveItem { product_id: String, quantity: f64 },
+}
+
+// Queued op - PRIVATE entry, written to the source chain only so it works with no peers around
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct QueuedCartOp {
+    // Chosen by the client - queueing or replaying the same op_id twice has no further effect
+    pub op_id: String,
+    pub op: CartOp,
+    pub queued_at: u64,
+}
+
+// Why a replayed op did not apply as queued
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum CartOpConflict {
+    // The order left Shopping while the device was offline
+    OrderNotEditable { status: String },
+    // Removed from another device first
+    ItemMissing { product_id: String },
+    // Less was left to remove than the op asked for - what was left was removed
+    QuantityClamped { requested: f64, removed: f64 },
+    // The cart refused the change (quantity limit, expired price quote...)
+    Rejected { reason: String },
+}
+
+// Result of replaying one queued op - PRIVATE entry, its presence marks the op as done
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct CartOpReceipt {
+    pub op_id: String,
+    pub queued_op_hash: ActionHash,
+    pub replayed_at: u64,
+    pub applied: bool,
+    pub cart_product_hash: Option<ActionHash>,
+    pub conflict: Option<CartOpConflict>,
+}
+
+pub fn validate_create_queued_cart_op(
+    _action: EntryCreationAction,
+    queued_op: QueuedCartOp,
+) -> ExternResult<ValidateCallbackResult> {
+    if queued_op.op_id.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Queued cart ops need an op id".to_string(),
+        ));
+    }
+    let quantity = match &queued_op.op {
+        CartOp::AddItem { quantity, .. } | CartOp::RemoveItem { quantity, .. } => *quantity,
+    };
+    if !quantity.is_finite() || quantity <= 0.0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Queued cart ops need a positive quantity".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
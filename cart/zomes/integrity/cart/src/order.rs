@@ -0,0 +1,190 @@
+use hdi::prelude::*;
+
+use crate::{cart_dna_properties, DeliveryTimeSlot, SessionStatus};
+
+// Shopper claim on a published cart session - PUBLIC DHT entry
+// One cart cell holds one customer session, so there is at most one live assignment per cell.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct OrderAssignment {
+    pub customer: AgentPubKey,
+    pub shopper: AgentPubKey,
+    // The "Checkout" SessionStatus record the shopper claimed
+    pub published_status_hash: ActionHash,
+    pub claimed_at: u64,
+}
+
+// Hours before the delivery slot the customer can still cancel - `order_cutoff_hours` in the cart DNA properties
+pub const DEFAULT_ORDER_CUTOFF_HOURS: u64 = 24;
+
+pub fn order_cutoff_hours() -> ExternResult<u64> {
+    Ok(cart_dna_properties()
+        .unwrap_or_default()
+        .order_cutoff_hours
+        .unwrap_or(DEFAULT_ORDER_CUTOFF_HOURS))
+}
+
+// Why an order was cancelled - CustomerRequest is the customer's alone, the store and delivery reasons are
+// the shopper's alone, and Other (with a note) is open to both
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CancellationReason {
+    CustomerRequest,
+    StoreClosed,
+    UnsafeAddress,
+    ItemsUnavailable,
+    CustomerUnreachable,
+    Other,
+}
+
+impl CancellationReason {
+    pub fn is_shopper_reason(&self) -> bool {
+        !matches!(self, CancellationReason::CustomerRequest | CancellationReason::Other)
+    }
+
+    pub fn is_customer_reason(&self) -> bool {
+        matches!(self, CancellationReason::CustomerRequest | CancellationReason::Other)
+    }
+
+    pub fn allowed_for_shopper(&self) -> bool {
+        self.is_shopper_reason() || *self == CancellationReason::Other
+    }
+}
+
+// Attached to the "Cancelled" SessionStatus
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrderCancellation {
+    pub cancelled_by: AgentPubKey,
+    pub reason: CancellationReason,
+    pub note: Option<String>,
+    // Set once the order was claimed - ties a shopper cancellation to their assignment
+    pub assignment_hash: Option<ActionHash>,
+    pub cancelled_at: u64,
+}
+
+// The customer can cancel until the slot cut-off, the assigned shopper at any point before delivery
+pub fn validate_order_cancellation(
+    author: &AgentPubKey,
+    customer: &AgentPubKey,
+    timestamp: &Timestamp,
+    session_status: &SessionStatus,
+    cancellation: &OrderCancellation,
+) -> ExternResult<ValidateCallbackResult> {
+    if cancellation.cancelled_by != *author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cancellations must be made by their author".to_string(),
+        ));
+    }
+    if cancellation.assignment_hash != session_status.assignment_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A cancellation must name the order's own assignment".to_string(),
+        ));
+    }
+    if cancellation.reason == CancellationReason::Other
+        && cancellation.note.as_deref().is_none_or(|note| note.trim().is_empty())
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Cancelling for another reason requires a note".to_string(),
+        ));
+    }
+
+    let assignment = match &cancellation.assignment_hash {
+        Some(assignment_hash) => match OrderAssignment::try_from(must_get_valid_record(assignment_hash.clone())?) {
+            Ok(assignment) => Some(assignment),
+            Err(_) => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "assignment_hash must point to an OrderAssignment".to_string(),
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let is_shopper = assignment.is_some_and(|assignment| assignment.shopper == *author);
+    if is_shopper {
+        if !cancellation.reason.allowed_for_shopper() {
+            return Ok(ValidateCallbackResult::Invalid(
+                "The assigned shopper cannot cancel with the customer's reason".to_string(),
+            ));
+        }
+        return Ok(ValidateCallbackResult::Valid);
+    }
+    if !cancellation.reason.is_customer_reason() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the assigned shopper can cancel for that reason".to_string(),
+        ));
+    }
+    if author != customer {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer or the assigned shopper can cancel an order".to_string(),
+        ));
+    }
+    validate_customer_cancellation_cutoff(timestamp, session_status)
+}
+
+// Slot dates are the UI's millisecond timestamps; action timestamps are microseconds
+fn validate_customer_cancellation_cutoff(
+    timestamp: &Timestamp,
+    session_status: &SessionStatus,
+) -> ExternResult<ValidateCallbackResult> {
+    let slot_hash = match &session_status.delivery_time_slot_hash {
+        Some(hash) => hash.clone(),
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+    let time_slot = match DeliveryTimeSlot::try_from(must_get_valid_record(slot_hash)?) {
+        Ok(time_slot) => time_slot,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "delivery_time_slot_hash must point to a DeliveryTimeSlot".to_string(),
+            ));
+        }
+    };
+
+    let cutoff_hours = order_cutoff_hours()?;
+    let cancelled_at = timestamp.as_millis() as u64;
+    if cancelled_at.saturating_add(cutoff_hours * 60 * 60 * 1000) > time_slot.date {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Customers can only cancel up to {} hours before the delivery slot",
+            cutoff_hours
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_create_order_assignment(
+    action: EntryCreationAction,
+    assignment: OrderAssignment,
+) -> ExternResult<ValidateCallbackResult> {
+    if *action.author() != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the shopper can author their own order assignment".to_string(),
+        ));
+    }
+    if assignment.shopper == assignment.customer {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A customer cannot claim their own order".to_string(),
+        ));
+    }
+
+    let status_record = must_get_valid_record(assignment.published_status_hash.clone())?;
+    let status = match SessionStatus::try_from(status_record.clone()) {
+        Ok(status) => status,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "published_status_hash must point to a SessionStatus".to_string(),
+            ));
+        }
+    };
+    if status.status != "Checkout" {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Only orders in Checkout can be claimed (found {})",
+            status.status
+        )));
+    }
+    if *status_record.action().author() != assignment.customer {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Assignment customer must be the agent who published the order".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,192 @@
+use hdi::prelude::*;
+
+use crate::{DeliveryProof, Money, SessionStatus};
+
+// Lifecycle of a payment intent
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PaymentStatus {
+    Created,
+    Authorized,
+    Captured,
+    Refunded,
+    Failed,
+}
+
+// Payment intent for this cart session's order - PUBLIC DHT entry
+// Updated in place as it moves through its lifecycle (same relink pattern as SessionStatus)
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct PaymentIntent {
+    pub payment_status: PaymentStatus,
+    pub provider: String,                    // e.g. "mock"
+    pub provider_reference: Option<String>,  // Provider-side id once authorized
+    pub currency: String,
+    pub authorized_amount: Money,            // Hold placed at checkout, including the weight buffer
+    pub captured_amount: Option<Money>,      // Final fulfillment total
+    pub refunded_amount: Option<Money>,
+    pub failure_reason: Option<String>,
+    pub updated_at: u64,
+    // The Delivered SessionStatus a capture is for - its delivery proof states the amount to capture
+    #[serde(default)]
+    pub capture_status_hash: Option<ActionHash>,
+}
+
+fn is_valid_amount(amount: &Money, currency: &str) -> bool {
+    !amount.is_negative() && amount.currency == currency
+}
+
+fn minor_or_zero(amount: &Option<Money>) -> i64 {
+    amount.as_ref().map_or(0, |amount| amount.amount_minor)
+}
+
+pub fn validate_create_payment_intent(
+    _action: EntryCreationAction,
+    payment_intent: PaymentIntent,
+) -> ExternResult<ValidateCallbackResult> {
+    if payment_intent.payment_status != PaymentStatus::Created {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Payment intents must be created in the Created status".to_string(),
+        ));
+    }
+    if !is_valid_amount(&payment_intent.authorized_amount, &payment_intent.currency) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Payment amount must be non-negative and in the intent's currency".to_string(),
+        ));
+    }
+    if payment_intent.captured_amount.is_some() || payment_intent.refunded_amount.is_some() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A new payment intent cannot carry captured or refunded amounts".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_update_payment_intent(
+    action: Update,
+    payment_intent: PaymentIntent,
+    original_payment_intent: PaymentIntent,
+) -> ExternResult<ValidateCallbackResult> {
+    use PaymentStatus::*;
+
+    let transition_allowed = matches!(
+        (&original_payment_intent.payment_status, &payment_intent.payment_status),
+        (Created, Authorized)
+            | (Created, Failed)
+            | (Authorized, Captured)
+            | (Authorized, Refunded)
+            | (Authorized, Failed)
+            | (Captured, Refunded)
+            | (Refunded, Refunded)
+    );
+    if !transition_allowed {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Invalid payment transition from {:?} to {:?}",
+            original_payment_intent.payment_status, payment_intent.payment_status
+        )));
+    }
+
+    if payment_intent.provider != original_payment_intent.provider
+        || payment_intent.currency != original_payment_intent.currency
+        || payment_intent.authorized_amount != original_payment_intent.authorized_amount
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Payment provider, currency and authorized amount cannot change".to_string(),
+        ));
+    }
+
+    if let Some(captured) = &payment_intent.captured_amount {
+        if !is_valid_amount(captured, &payment_intent.currency)
+            || captured.amount_minor > payment_intent.authorized_amount.amount_minor
+        {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Captured amount cannot exceed the authorized amount".to_string(),
+            ));
+        }
+    }
+    if original_payment_intent.captured_amount.is_some()
+        && payment_intent.captured_amount != original_payment_intent.captured_amount
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Captured amount cannot change once captured".to_string(),
+        ));
+    }
+    if payment_intent.payment_status == Captured && original_payment_intent.payment_status == Authorized {
+        if let Some(reason) = validate_capture(&action, &payment_intent)? {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+
+    if payment_intent.payment_status == PaymentStatus::Refunded && payment_intent.refunded_amount.is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A refunded payment must record the refunded amount in its currency".to_string(),
+        ));
+    }
+    if let Some(refunded) = &payment_intent.refunded_amount {
+        let refundable = minor_or_zero(&payment_intent.captured_amount);
+        if !is_valid_amount(refunded, &payment_intent.currency) || refunded.amount_minor > refundable {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Refunded amount cannot exceed the captured amount".to_string(),
+            ));
+        }
+        if refunded.amount_minor < minor_or_zero(&original_payment_intent.refunded_amount) {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Refunded amount cannot decrease".to_string(),
+            ));
+        }
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// A capture is only for a delivered order published with this authorization, and only for the
+// total the shopper stated in the delivery proof
+fn validate_capture(action: &Update, payment_intent: &PaymentIntent) -> ExternResult<Option<String>> {
+    let status_hash = match &payment_intent.capture_status_hash {
+        Some(hash) => hash.clone(),
+        None => return Ok(Some("A capture must name the Delivered status it is for".to_string())),
+    };
+    let status = match SessionStatus::try_from(must_get_valid_record(status_hash)?) {
+        Ok(status) if status.status == "Delivered" => status,
+        _ => return Ok(Some("Payment can only be captured for a Delivered order".to_string())),
+    };
+    if status.payment_intent_hash.as_ref() != Some(&action.original_action_address) {
+        return Ok(Some("The captured authorization must be the one the order was published with".to_string()));
+    }
+
+    let proof_hash = match &status.delivery_proof_hash {
+        Some(hash) => hash.clone(),
+        None => return Ok(Some("The Delivered status has no delivery proof".to_string())),
+    };
+    let proof = match DeliveryProof::try_from(must_get_valid_record(proof_hash)?) {
+        Ok(proof) => proof,
+        Err(_) => return Ok(Some("delivery_proof_hash must point to a DeliveryProof".to_string())),
+    };
+    match proof.delivered_total {
+        Some(total) if payment_intent.captured_amount.as_ref() == Some(&total) => Ok(None),
+        Some(_) => Ok(Some("The captured amount must be the delivered total from the delivery proof".to_string())),
+        None => Ok(Some("The delivery proof does not state a delivered total".to_string())),
+    }
+}
+
+// The published order must be backed by an Authorized intent of the customer publishing it
+pub fn validate_published_payment_intent(
+    customer: &AgentPubKey,
+    payment_intent_hash: &Option<ActionHash>,
+) -> ExternResult<Option<String>> {
+    let payment_intent_hash = match payment_intent_hash {
+        Some(hash) => hash.clone(),
+        None => return Ok(Some("Payment must be authorized before publishing the order".to_string())),
+    };
+    let record = must_get_valid_record(payment_intent_hash)?;
+    if record.action().author() != customer {
+        return Ok(Some("The published order must be paid by its customer".to_string()));
+    }
+    match PaymentIntent::try_from(record) {
+        Ok(payment_intent) if payment_intent.payment_status == PaymentStatus::Authorized => Ok(None),
+        Ok(payment_intent) => Ok(Some(format!(
+            "Payment must be authorized before publishing the order (status is {:?})",
+            payment_intent.payment_status
+        ))),
+        Err(_) => Ok(Some("payment_intent_hash must point to a PaymentIntent".to_string())),
+    }
+}
@@ -0,0 +1,132 @@
+use hdi::prelude::*;
+
+use crate::{
+    validate_age_restricted_category, validate_barcode_scan, validate_cart_product_prices,
+    validate_cart_product_quote, validate_product_ref, BarcodeScan, CartProduct, OrderAssignment,
+};
+
+// Shopper's record of what was actually picked for one cart line - PUBLIC DHT entry
+// Linked from the CartProduct via CartProductToPick; the most recent pick is authoritative.
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct PickRecord {
+    pub assignment_hash: ActionHash,
+    pub cart_product_hash: ActionHash,
+    // Units or weight actually picked - 0 means the line could not be filled
+    pub picked_quantity: f64,
+    pub picked_at: u64,
+    // Snapshot of the replacement product when the shopper substituted; picked_quantity applies to it
+    #[serde(default)]
+    pub substitute: Option<CartProduct>,
+    // Set when the pick came from a verified barcode scan
+    #[serde(default)]
+    pub scan: Option<BarcodeScan>,
+}
+
+pub fn validate_create_pick_record(
+    action: EntryCreationAction,
+    pick_record: PickRecord,
+) -> ExternResult<ValidateCallbackResult> {
+    if !pick_record.picked_quantity.is_finite() || pick_record.picked_quantity < 0.0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Picked quantity must be a non-negative number".to_string(),
+        ));
+    }
+
+    let assignment_record = must_get_valid_record(pick_record.assignment_hash.clone())?;
+    let assignment = match OrderAssignment::try_from(assignment_record) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if *action.author() != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the assigned shopper can record picks".to_string(),
+        ));
+    }
+
+    if let Some(substitute) = &pick_record.substitute {
+        if substitute.product_name.is_empty() {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Substitutes need a product name".to_string(),
+            ));
+        }
+        if let Some(reason) = validate_product_ref(&substitute.product_id) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+        if let Some(reason) = validate_cart_product_prices(substitute) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+        if let Some(reason) = validate_age_restricted_category(substitute) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+        // The shopper's substitute price is held to a signed catalog quote just like the customer's lines
+        if let Some(reason) = validate_cart_product_quote(&action, substitute)? {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+
+    if let Some(scan) = &pick_record.scan {
+        if let Some(reason) = validate_barcode_scan(scan) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+
+    let cart_product_record = must_get_valid_record(pick_record.cart_product_hash.clone())?;
+    if *cart_product_record.action().author() != assignment.customer
+        || CartProduct::try_from(cart_product_record).is_err()
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "cart_product_hash must point to one of the customer's cart lines".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Pick links (cart line -> pick) may only be created by the assigned shopper, from the pick's own cart line
+pub fn validate_create_pick_link(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let pick_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Pick links must point to a PickRecord action".to_string(),
+            ));
+        }
+    };
+    let pick = match PickRecord::try_from(must_get_valid_record(pick_hash)?) {
+        Ok(pick) => pick,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Pick links must point to a PickRecord".to_string(),
+            ));
+        }
+    };
+    if base_address != AnyLinkableHash::from(pick.cart_product_hash) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Picks can only be linked from the cart line they were recorded for".to_string(),
+        ));
+    }
+
+    let assignment = match OrderAssignment::try_from(must_get_valid_record(pick.assignment_hash)?) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    if action.author != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the assigned shopper can link picks".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,46 @@
+use hdi::prelude::*;
+
+use crate::{cart_dna_properties, CartProduct};
+
+// The products DNA admin key, as `price_quote_signer` in the cart DNA properties - malformed properties
+// are an error, never "no signer configured"
+fn price_quote_signer() -> ExternResult<Option<AgentPubKey>> {
+    match cart_dna_properties()?.price_quote_signer {
+        Some(key) => AgentPubKey::try_from(key)
+            .map(Some)
+            .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Invalid price_quote_signer: {:?}", e)))),
+        None => Ok(None),
+    }
+}
+
+// A cart line's prices must come from an unexpired quote signed by the configured admin.
+// Networks without a price_quote_signer keep accepting client prices.
+pub fn validate_cart_product_quote(
+    action: &EntryCreationAction,
+    cart_product: &CartProduct,
+) -> ExternResult<Option<String>> {
+    let signer = match price_quote_signer() {
+        Ok(Some(signer)) => signer,
+        Ok(None) => return Ok(None),
+        Err(e) => return Ok(Some(format!("Price quotes cannot be checked against the DNA properties: {:?}", e))),
+    };
+    let signed = match &cart_product.price_quote {
+        Some(signed) => signed,
+        None => return Ok(Some("Cart products need a signed price quote".to_string())),
+    };
+
+    let quote = &signed.quote;
+    if quote.product_id != cart_product.product_id {
+        return Ok(Some("Price quote is for another product".to_string()));
+    }
+    if quote.price != cart_product.price_at_checkout || quote.promo_price != cart_product.promo_price {
+        return Ok(Some("Cart prices do not match the signed price quote".to_string()));
+    }
+    if action.timestamp().as_micros() as u64 > quote.expires_at {
+        return Ok(Some("Price quote has expired".to_string()));
+    }
+    if !verify_signature(signer, signed.signature.clone(), quote)? {
+        return Ok(Some("Price quote is not signed by the store admin".to_string()));
+    }
+    Ok(None)
+}
@@ -0,0 +1,10 @@
+use catalog::ProductRef;
+
+// A cart product id must be a serialized ProductRef in its canonical form
+pub fn validate_product_ref(product_id: &str) -> Option<String> {
+    match ProductRef::parse(product_id) {
+        Ok(product_ref) if product_ref.to_string() == product_id => None,
+        Ok(_) => Some(format!("Product id {} is not in canonical ProductRef form", product_id)),
+        Err(reason) => Some(reason),
+    }
+}
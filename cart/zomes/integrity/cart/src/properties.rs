@@ -0,0 +1,37 @@
+use hdi::prelude::*;
+
+use crate::{Money, StoreLocation};
+
+// Circle around the store that orders can be delivered to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeliveryZone {
+    pub center_lat: f64,
+    pub center_lng: f64,
+    pub radius_meters: f64,
+}
+
+// Optional cart DNA properties - every field falls back to a default so `properties: null` keeps working.
+// Read by validation here and by the coordinator through get_cart_properties.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CartDnaProperties {
+    pub average_speed_kmh: Option<f64>,
+    // Hours before the delivery date an order must be confirmed
+    pub order_cutoff_hours: Option<u64>,
+    // Smallest cart total that can be checked out - no minimum when absent
+    pub minimum_basket: Option<Money>,
+    // Delivery addresses must fall inside this zone - unrestricted when absent
+    pub delivery_zone: Option<DeliveryZone>,
+    // Stores offering click-and-collect - any store the customer names is accepted when absent
+    pub pickup_locations: Option<Vec<StoreLocation>>,
+    // Store this cart network's delivery orders are shopped at - orders are only batched within one store
+    pub store_id: Option<String>,
+    // Products DNA admin key - cart products then need a price quote it signed
+    pub price_quote_signer: Option<String>,
+}
+
+// Decode the DNA properties - absent properties are the defaults, malformed ones an error
+pub fn cart_dna_properties() -> ExternResult<CartDnaProperties> {
+    let decoded: Result<Option<CartDnaProperties>, _> = decode(dna_info()?.modifiers.properties.bytes());
+    decoded.map(Option::unwrap_or_default).map_err(|e| wasm_error!(e))
+}
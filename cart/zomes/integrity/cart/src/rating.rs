@@ -0,0 +1,139 @@
+use hdi::prelude::*;
+
+use crate::{OrderAssignment, SessionStatus};
+
+// Post-delivery rating left by one party of an order for the other - PUBLIC DHT entry
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct Rating {
+    pub assignment_hash: ActionHash,
+    // The "Delivered" SessionStatus record that makes the order rateable
+    pub delivered_status_hash: ActionHash,
+    pub ratee: AgentPubKey,
+    pub stars: u8,             // 1-5
+    pub tags: Vec<String>,     // e.g. "great substitutions", "on time"
+    pub comment: Option<String>,
+    pub rated_at: u64,
+}
+
+pub const MAX_RATING_TAGS: usize = 10;
+pub const MAX_RATING_COMMENT_LENGTH: usize = 1000;
+
+pub fn validate_create_rating(
+    action: EntryCreationAction,
+    rating: Rating,
+) -> ExternResult<ValidateCallbackResult> {
+    if rating.stars < 1 || rating.stars > 5 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Rating stars must be between 1 and 5".to_string(),
+        ));
+    }
+    if rating.tags.len() > MAX_RATING_TAGS {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "A rating can carry at most {} tags",
+            MAX_RATING_TAGS
+        )));
+    }
+    if let Some(comment) = &rating.comment {
+        if comment.len() > MAX_RATING_COMMENT_LENGTH {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "Rating comment cannot exceed {} characters",
+                MAX_RATING_COMMENT_LENGTH
+            )));
+        }
+    }
+
+    // Only the two parties of the assignment may rate, and only each other
+    let assignment_record = must_get_valid_record(rating.assignment_hash.clone())?;
+    let assignment = match OrderAssignment::try_from(assignment_record) {
+        Ok(assignment) => assignment,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "assignment_hash must point to an OrderAssignment".to_string(),
+            ));
+        }
+    };
+    let author = action.author().clone();
+    let expected_ratee = if author == assignment.customer {
+        assignment.shopper.clone()
+    } else if author == assignment.shopper {
+        assignment.customer.clone()
+    } else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the customer and shopper of an order can rate it".to_string(),
+        ));
+    };
+    if rating.ratee != expected_ratee {
+        return Ok(ValidateCallbackResult::Invalid(
+            "The ratee must be the other party of the order".to_string(),
+        ));
+    }
+
+    // The order must have been marked Delivered by its shopper
+    let status_record = must_get_valid_record(rating.delivered_status_hash.clone())?;
+    let status_author = status_record.action().author().clone();
+    match SessionStatus::try_from(status_record) {
+        Ok(status) if status.status == "Delivered" => {}
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Only delivered orders can be rated".to_string(),
+            ));
+        }
+    }
+    if status_author != assignment.shopper {
+        return Ok(ValidateCallbackResult::Invalid(
+            "The Delivered status must be authored by the assigned shopper".to_string(),
+        ));
+    }
+
+    // Once each: a cart cell holds a single order, so any earlier Rating on the author's chain is a duplicate
+    let activity = must_get_agent_activity(author, ChainFilter::new(action.prev_action().clone()))?;
+    let already_rated = activity.iter().any(|item| {
+        matches!(item.action.action(), Action::Create(_))
+            && item.action.action().entry_type() == Some(action.entry_type())
+    });
+    if already_rated {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Each party can rate an order only once".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Rating link (ratee -> rating) - only the rater can file a rating, and only under its ratee
+pub fn validate_create_rating_link(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let rating_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Rating links must point to a Rating action".to_string(),
+            ));
+        }
+    };
+    let rating_record = must_get_valid_record(rating_hash)?;
+    let rater = rating_record.action().author().clone();
+    let rating = match Rating::try_from(rating_record) {
+        Ok(rating) => rating,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Rating links must point to a Rating".to_string(),
+            ));
+        }
+    };
+    if base_address != AnyLinkableHash::from(rating.ratee) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A rating can only be linked from the agent it rates".to_string(),
+        ));
+    }
+    if action.author != rater {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the rater can link their rating".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
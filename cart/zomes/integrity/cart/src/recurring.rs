@@ -0,0 +1,79 @@
+use hdi::prelude::*;
+
+use crate::{validate_cart_product_prices, Address, CartProduct};
+
+// Milliseconds per cadence step - recurring dates use the same millisecond timestamps the UI writes to DeliveryTimeSlot.date
+pub const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RecurrenceCadence {
+    Weekly,
+    Biweekly,
+}
+
+impl RecurrenceCadence {
+    pub fn interval_millis(&self) -> u64 {
+        match self {
+            RecurrenceCadence::Weekly => 7 * MILLIS_PER_DAY,
+            RecurrenceCadence::Biweekly => 14 * MILLIS_PER_DAY,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RecurringOrderStatus {
+    Active,
+    Paused,
+    Cancelled,
+}
+
+// One templated cart line
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecurringOrderLine {
+    pub product: CartProduct,
+    pub quantity: f64,
+}
+
+// Customer's standing order template - PRIVATE entry, updated in place as it is paused, skipped or materialized
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct RecurringOrder {
+    pub lines: Vec<RecurringOrderLine>,
+    pub cadence: RecurrenceCadence,
+    pub time_slot: String, // Preferred window, same format as DeliveryTimeSlot.time_slot (e.g. "2pm-4pm")
+    // The saved address this template delivers to, plus the snapshot sealed into each materialized cart
+    pub address_hash: ActionHash,
+    pub address: Address,
+    pub next_delivery_date: u64,
+    pub status: RecurringOrderStatus,
+    pub skip_next: bool,
+    pub last_materialized_at: Option<u64>,
+    pub created_at: u64,
+}
+
+pub fn validate_create_recurring_order(
+    _action: EntryCreationAction,
+    recurring_order: RecurringOrder,
+) -> ExternResult<ValidateCallbackResult> {
+    if recurring_order.lines.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A recurring order needs at least one line".to_string(),
+        ));
+    }
+    for line in &recurring_order.lines {
+        if !line.quantity.is_finite() || line.quantity <= 0.0 {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Recurring order quantities must be positive".to_string(),
+            ));
+        }
+        if let Some(reason) = validate_cart_product_prices(&line.product) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+    if recurring_order.time_slot.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A recurring order needs a preferred delivery window".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,269 @@
+use hdi::prelude::*;
+
+use crate::{validate_cart_product_prices, CartProduct};
+
+pub const MAX_SHOPPING_LIST_NAME_LENGTH: usize = 100;
+pub const MAX_SHOPPING_LIST_ITEM_LENGTH: usize = 200;
+
+// A named list owned by one customer and shared with others - PUBLIC DHT entry
+// Updated in place by the owner to rename or change who it is shared with
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct ShoppingList {
+    pub name: String,
+    pub owner: AgentPubKey,
+    pub members: Vec<AgentPubKey>,
+    pub updated_at: u64,
+}
+
+impl ShoppingList {
+    pub fn can_edit(&self, agent: &AgentPubKey) -> bool {
+        self.owner == *agent || self.members.contains(agent)
+    }
+}
+
+// One line of a list - free text ("2 lemons") or a catalog-linked CartProduct snapshot - PUBLIC DHT entry
+// Updated in place to check it off; the list links to the current version
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct ShoppingListItem {
+    pub list_hash: ActionHash,         // First version of the list - the stable id
+    pub list_version_hash: ActionHash, // The current version of the list - it must name the author as owner or member
+    pub owner_chain_top: ActionHash,   // The owner's chain head the version was read from
+    pub text: String,
+    pub quantity: f64,
+    pub product: Option<CartProduct>,
+    pub checked: bool,
+    pub checked_by: Option<AgentPubKey>,
+    pub updated_at: u64,
+}
+
+pub fn validate_create_shopping_list(
+    action: EntryCreationAction,
+    shopping_list: ShoppingList,
+) -> ExternResult<ValidateCallbackResult> {
+    if *action.author() != shopping_list.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Shopping lists can only be created by their owner".to_string(),
+        ));
+    }
+    if shopping_list.name.trim().is_empty() || shopping_list.name.chars().count() > MAX_SHOPPING_LIST_NAME_LENGTH {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Shopping list names must be between 1 and {} characters",
+            MAX_SHOPPING_LIST_NAME_LENGTH
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_update_shopping_list(
+    action: Update,
+    shopping_list: ShoppingList,
+    original_shopping_list: ShoppingList,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.author != original_shopping_list.owner || shopping_list.owner != original_shopping_list.owner {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the owner can change a shopping list".to_string(),
+        ));
+    }
+    validate_create_shopping_list(EntryCreationAction::Update(action), shopping_list)
+}
+
+// The newest version of a list on its owner's chain up to `owner_chain_top` - None when the list
+// is not on that stretch of the chain. Only the owner updates a list, so its versions are all there.
+pub fn latest_list_version(
+    owner: AgentPubKey,
+    owner_chain_top: ActionHash,
+    list_hash: &ActionHash,
+) -> ExternResult<Option<ActionHash>> {
+    let mut activity = must_get_agent_activity(owner, ChainFilter::new(owner_chain_top).until(list_hash.clone()))?;
+    activity.sort_by_key(|item| item.action.action().action_seq());
+    if !activity.iter().any(|item| item.action.as_hash() == list_hash) {
+        return Ok(None);
+    }
+
+    let mut latest = list_hash.clone();
+    for item in activity {
+        if let Action::Update(update) = item.action.action() {
+            if update.original_action_address == latest {
+                latest = item.action.as_hash().clone();
+            }
+        }
+    }
+    Ok(Some(latest))
+}
+
+pub fn validate_create_shopping_list_item(
+    action: EntryCreationAction,
+    item: ShoppingListItem,
+) -> ExternResult<ValidateCallbackResult> {
+    if item.text.trim().is_empty() && item.product.is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "List items need text or a product".to_string(),
+        ));
+    }
+    if item.text.chars().count() > MAX_SHOPPING_LIST_ITEM_LENGTH {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "List items cannot exceed {} characters",
+            MAX_SHOPPING_LIST_ITEM_LENGTH
+        )));
+    }
+    if !item.quantity.is_finite() || item.quantity <= 0.0 {
+        return Ok(ValidateCallbackResult::Invalid(
+            "List item quantities must be positive".to_string(),
+        ));
+    }
+    if let Some(product) = &item.product {
+        if let Some(reason) = validate_cart_product_prices(product) {
+            return Ok(ValidateCallbackResult::Invalid(reason));
+        }
+    }
+
+    // Membership is checked against the list as it stands on the owner's chain, not any version the author picks
+    let owner = match ShoppingList::try_from(must_get_valid_record(item.list_hash.clone())?) {
+        Ok(shopping_list) => shopping_list.owner,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "list_hash must point to a ShoppingList".to_string(),
+            ));
+        }
+    };
+    if latest_list_version(owner, item.owner_chain_top.clone(), &item.list_hash)?.as_ref()
+        != Some(&item.list_version_hash)
+    {
+        return Ok(ValidateCallbackResult::Invalid(
+            "list_version_hash must be the list's current version on its owner's chain".to_string(),
+        ));
+    }
+    let shopping_list = match ShoppingList::try_from(must_get_valid_record(item.list_version_hash.clone())?) {
+        Ok(shopping_list) => shopping_list,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "list_version_hash must point to a ShoppingList".to_string(),
+            ));
+        }
+    };
+    if !shopping_list.can_edit(action.author()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the owner and members can edit a shopping list".to_string(),
+        ));
+    }
+    if item.checked != item.checked_by.is_some() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Checked items must record who checked them".to_string(),
+        ));
+    }
+
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_update_shopping_list_item(
+    action: Update,
+    item: ShoppingListItem,
+    original_item: ShoppingListItem,
+) -> ExternResult<ValidateCallbackResult> {
+    if item.list_hash != original_item.list_hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "List items cannot move between lists".to_string(),
+        ));
+    }
+    validate_create_shopping_list_item(EntryCreationAction::Update(action), item)
+}
+
+// Only the list owner can add a list to someone's "shared with me" links
+pub fn validate_create_agent_to_shopping_list_link(
+    action: CreateLink,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let list_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Shopping list links must target a ShoppingList action".to_string(),
+            ));
+        }
+    };
+    let shopping_list = match ShoppingList::try_from(must_get_valid_record(list_hash)?) {
+        Ok(shopping_list) => shopping_list,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Shopping list links must target a ShoppingList".to_string(),
+            ));
+        }
+    };
+    if shopping_list.owner != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the owner can share a shopping list".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Item links (list -> item) are written by whoever wrote that item version, from the item's own list
+pub fn validate_create_shopping_list_item_link(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let item_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "List item links must point to a ShoppingListItem action".to_string(),
+            ));
+        }
+    };
+    let item_record = must_get_valid_record(item_hash)?;
+    if *item_record.action().author() != action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "List items can only be linked by the agent who wrote them".to_string(),
+        ));
+    }
+    let item = match ShoppingListItem::try_from(item_record) {
+        Ok(item) => item,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "List item links must point to a ShoppingListItem".to_string(),
+            ));
+        }
+    };
+    if base_address != AnyLinkableHash::from(item.list_hash) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "List items can only be linked from their own list".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Only the owner and the members named by the item's list version can unlink it
+pub fn validate_delete_shopping_list_item_link(
+    action: DeleteLink,
+    target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let item_hash = match target_address.into_action_hash() {
+        Some(hash) => hash,
+        None => return Ok(ValidateCallbackResult::Invalid("List item links point to actions".to_string())),
+    };
+    let item = match ShoppingListItem::try_from(must_get_valid_record(item_hash)?) {
+        Ok(item) => item,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "List item links must point to a ShoppingListItem".to_string(),
+            ));
+        }
+    };
+    let shopping_list = match ShoppingList::try_from(must_get_valid_record(item.list_version_hash)?) {
+        Ok(shopping_list) => shopping_list,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "list_version_hash must point to a ShoppingList".to_string(),
+            ));
+        }
+    };
+    if !shopping_list.can_edit(&action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the owner and members can remove list items".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
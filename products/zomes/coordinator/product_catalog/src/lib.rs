@@ -1,6 +1,9 @@
+pub mod price_quote;
 pub mod product;
+pub mod product_ref;
 pub mod products_by_category;
 pub mod search;
+pub mod store_layout;
 mod utils;
 
 use hdk::prelude::*;
@@ -13,28 +16,12 @@ fn get_cell_id_debug_info() -> ExternResult<String> {
     Ok(format!("{:?}:{:?}", dna_info.hash, agent_info.agent_initial_pubkey))
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DnaProperties {
-    admin_pub_key_str: String,
-}
-
 #[hdk_extern]
 fn is_admin(_: ()) -> ExternResult<bool> {
-    let agent_info = agent_info()?;
-    let caller_pub_key = agent_info.agent_initial_pubkey;
-
-    let dna_info = dna_info()?;
-    let properties_sb = dna_info.modifiers.properties; // This is SerializedBytes
-    let properties: DnaProperties = hdk::prelude::decode(properties_sb.bytes())
-        .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Failed to decode DNA properties: {:?}", e))))?;
-
-    let caller_str = caller_pub_key.to_string();
-    let admin_str = properties.admin_pub_key_str.clone(); // Clone for logging if needed, or use as is.
-
-    warn!("[is_admin] Caller PubKey: {}", caller_str);
-    warn!("[is_admin] Admin PubKey from Props: {}", admin_str);
+    let caller_pub_key = agent_info()?.agent_initial_pubkey;
+    let is_match = is_admin_agent(&caller_pub_key)?;
 
-    let is_match = caller_str == admin_str;
+    warn!("[is_admin] Caller PubKey: {}", caller_pub_key);
     warn!("[is_admin] Comparison result (is_match): {}", is_match);
 
     Ok(is_match)
@@ -43,6 +30,7 @@ fn is_admin(_: ()) -> ExternResult<bool> {
 // Called the first time a zome call is made to the cell containing this zome
 #[hdk_extern]
 pub fn init() -> ExternResult<InitCallbackResult> {
+    price_quote::grant_price_quote_capability()?;
     Ok(InitCallbackResult::Pass)
 }
 
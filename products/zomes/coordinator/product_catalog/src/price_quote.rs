@@ -0,0 +1,67 @@
+use hdk::prelude::*;
+use products_integrity::*;
+
+use crate::product_ref::resolve_product;
+
+// How long a signed quote can be used to add the product to a cart
+pub const PRICE_QUOTE_VALIDITY_MICROS: u64 = 2 * 60 * 60 * 1_000_000;
+
+// Let any agent ask the admin's node for quotes
+pub(crate) fn grant_price_quote_capability() -> ExternResult<()> {
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, "quote_prices".into()));
+
+    create_cap_grant(CapGrantEntry {
+        tag: "price_quotes".into(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+
+    Ok(())
+}
+
+// Runs on the admin's node: sign the catalog's current price for each product id. The price comes
+// from the catalog, never from the caller, so a quote can only vouch for what the admin published.
+#[hdk_extern]
+pub fn quote_prices(product_ids: Vec<String>) -> ExternResult<Vec<SignedPriceQuote>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    if !is_admin_agent(&me)? {
+        return Err(wasm_error!(WasmErrorInner::Guest("Only the admin can sign price quotes".into())));
+    }
+
+    let expires_at = sys_time()?.as_micros() as u64 + PRICE_QUOTE_VALIDITY_MICROS;
+    let mut quotes = Vec::new();
+    for product_id in product_ids {
+        let product_ref = ProductRef::parse(&product_id).map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+        let product = resolve_product(&product_ref)?.ok_or(wasm_error!(WasmErrorInner::Guest(
+            format!("Product {} is not in the catalog", product_id)
+        )))?;
+        let quote = PriceQuote {
+            product_id,
+            price: product.price,
+            promo_price: product.promo_price,
+            expires_at,
+        };
+        let signature = sign(me.clone(), quote.clone())?;
+        quotes.push(SignedPriceQuote { quote, signature });
+    }
+
+    warn!("💲 PRICE QUOTES: Signed {} quotes", quotes.len());
+    Ok(quotes)
+}
+
+// Ask the admin for quotes to embed in cart products - the cart DNA rejects unsigned prices
+#[hdk_extern]
+pub fn request_price_quotes(product_ids: Vec<String>) -> ExternResult<Vec<SignedPriceQuote>> {
+    let admin = admin_agent_pub_key()?;
+    if admin == agent_info()?.agent_initial_pubkey {
+        return quote_prices(product_ids);
+    }
+
+    match call_remote(admin, zome_info()?.name, "quote_prices".into(), None, product_ids)? {
+        ZomeCallResponse::Ok(output) => output.decode().map_err(|err| wasm_error!(err)),
+        other => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "The admin could not quote prices: {:?}", other
+        )))),
+    }
+}
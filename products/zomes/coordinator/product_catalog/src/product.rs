@@ -107,7 +107,8 @@ pub fn create_product_group(input: CreateProductGroupInput) -> ExternResult<Acti
         category: input.category.clone(),
         subcategory: input.subcategory.clone(),
         product_type: input.product_type.clone(),
-        products: input.products,
+        // Stamp every product with the current (Money) schema version
+        products: input.products.into_iter().map(Product::migrated).collect(),
         additional_categorizations: input.additional_categorizations.clone(),
     };
 
@@ -0,0 +1,48 @@
+use hdk::prelude::*;
+use products_integrity::*;
+
+fn matches_stable_ids(product: &Product, product_ref: &ProductRef) -> bool {
+    let id_matches = product_ref.product_id.as_ref().is_none_or(|id| product.product_id.as_ref() == Some(id));
+    let upc_matches = product_ref.upc.as_ref().is_none_or(|upc| product.upc.as_ref() == Some(upc));
+    id_matches && upc_matches
+}
+
+// Find the product a ref points at. The index is tried first; when the ref carries a stable id or
+// UPC that no longer matches that slot, the rest of the group is searched for it.
+pub(crate) fn resolve_product(product_ref: &ProductRef) -> ExternResult<Option<Product>> {
+    if let Some(catalog) = &product_ref.catalog {
+        if *catalog != dna_info()?.modifiers.network_seed {
+            warn!("🔗 PRODUCT REF: {} is from catalog {}, not this one", product_ref, catalog);
+            return Ok(None);
+        }
+    }
+
+    let group = match get(product_ref.group_hash.clone(), GetOptions::default())? {
+        Some(record) => match ProductGroup::try_from(record) {
+            Ok(group) => group,
+            Err(_) => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+
+    let at_index = group.products.get(product_ref.index as usize);
+    if let Some(product) = at_index.filter(|product| matches_stable_ids(product, product_ref)) {
+        return Ok(Some(product.clone()));
+    }
+    if product_ref.product_id.is_none() && product_ref.upc.is_none() {
+        return Ok(None);
+    }
+    Ok(group.products.into_iter().find(|product| matches_stable_ids(product, product_ref)))
+}
+
+#[hdk_extern]
+pub fn resolve_product_ref(product_ref: ProductRef) -> ExternResult<Option<Product>> {
+    resolve_product(&product_ref)
+}
+
+// Same, for the string form stored as a cart product's product_id
+#[hdk_extern]
+pub fn resolve_product_id(product_id: String) -> ExternResult<Option<Product>> {
+    let product_ref = ProductRef::parse(&product_id).map_err(|reason| wasm_error!(WasmErrorInner::Guest(reason)))?;
+    resolve_product(&product_ref)
+}
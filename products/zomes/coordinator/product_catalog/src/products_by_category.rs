@@ -287,11 +287,12 @@ pub fn get_paginated_products_from_group(params: GroupProductsParams) -> ExternR
 
     let total = product_group.products.len();
 
-    // Apply pagination
+    // Apply pagination - legacy (float price) products are returned in the current Money schema
     let products: Vec<Product> = product_group.products
         .into_iter()
         .skip(params.offset)
         .take(params.limit)
+        .map(Product::migrated)
         .collect();
 
     let has_more = (params.offset + params.limit) < total;
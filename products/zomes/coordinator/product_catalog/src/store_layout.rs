@@ -0,0 +1,54 @@
+use hdk::prelude::*;
+use products_integrity::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetStoreLayoutInput {
+    pub store_id: String,
+    pub locations: Vec<AisleLocation>,
+}
+
+fn store_layout_path(store_id: &str) -> ExternResult<Path> {
+    Ok(Path::from(format!("store_layouts/{}", store_id)))
+}
+
+// Admin replaces a store's floor plan - the previous layout is unlinked
+#[hdk_extern]
+pub fn set_store_layout(input: SetStoreLayoutInput) -> ExternResult<ActionHash> {
+    if !is_admin_agent(&agent_info()?.agent_initial_pubkey)? {
+        return Err(wasm_error!(WasmErrorInner::Guest("Only the admin can maintain store layouts".into())));
+    }
+
+    let path_hash = store_layout_path(&input.store_id)?.path_entry_hash()?;
+    let links = get_links(GetLinksInputBuilder::try_new(path_hash.clone(), LinkTypes::StoreToLayout)?.build())?;
+
+    let store_layout = StoreLayout {
+        store_id: input.store_id,
+        locations: input.locations,
+        updated_at: sys_time()?.as_micros() as u64,
+    };
+    let layout_hash = create_entry(&EntryTypes::StoreLayout(store_layout))?;
+
+    for link in links {
+        delete_link(link.create_link_hash)?;
+    }
+    create_link(path_hash, layout_hash.clone(), LinkTypes::StoreToLayout, ())?;
+
+    Ok(layout_hash)
+}
+
+// Current floor plan of a store - passed by the shopper app to the cart DNA's get_pick_list
+#[hdk_extern]
+pub fn get_store_layout(store_id: String) -> ExternResult<Option<StoreLayout>> {
+    let path_hash = store_layout_path(&store_id)?.path_entry_hash()?;
+    let links = get_links(GetLinksInputBuilder::try_new(path_hash, LinkTypes::StoreToLayout)?.build())?;
+
+    let latest = links.into_iter().max_by_key(|link| link.timestamp);
+    let layout_hash = match latest.and_then(|link| link.target.into_action_hash()) {
+        Some(hash) => hash,
+        None => return Ok(None),
+    };
+    match get(layout_hash, GetOptions::default())? {
+        Some(record) => Ok(StoreLayout::try_from(record).ok()),
+        None => Ok(None),
+    }
+}
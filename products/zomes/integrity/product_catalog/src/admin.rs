@@ -0,0 +1,19 @@
+use hdi::prelude::*;
+
+// The products DNA properties - `admin_pub_key_str` names the agent who maintains the catalog
+#[derive(Serialize, Deserialize, Debug)]
+struct AdminProperties {
+    admin_pub_key_str: String,
+}
+
+// The admin key, read by validation here and by the coordinator (is_admin, store layouts, price quotes)
+pub fn admin_agent_pub_key() -> ExternResult<AgentPubKey> {
+    let properties: AdminProperties = decode(dna_info()?.modifiers.properties.bytes())
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Failed to decode DNA properties: {:?}", e))))?;
+    AgentPubKey::try_from(properties.admin_pub_key_str)
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Invalid admin key in DNA properties: {:?}", e))))
+}
+
+pub fn is_admin_agent(agent: &AgentPubKey) -> ExternResult<bool> {
+    Ok(*agent == admin_agent_pub_key()?)
+}
@@ -1,7 +1,15 @@
+pub use catalog; // Shared with cart_integrity
+pub use money; // Shared with cart_integrity
+pub mod admin;
 pub mod product;
+pub mod store_layout;
 use hdi::prelude::*;
 
+pub use admin::*;
+pub use catalog::*;
+pub use money::*;
 pub use product::*;
+pub use store_layout::*;
 
 
 #[derive(Serialize, Deserialize)]
@@ -9,14 +17,17 @@ pub use product::*;
 #[hdk_entry_types]
 #[unit_enum(UnitEntryTypes)]
 pub enum EntryTypes {
-    Product(Product),
+    // Boxed - a Product with its Money prices dwarfs the other variants
+    Product(Box<Product>),
     ProductGroup(ProductGroup),
+    StoreLayout(StoreLayout),
 }
 
 #[derive(Serialize, Deserialize)]
 #[hdk_link_types]
 pub enum LinkTypes {
     ProductTypeToGroup,
+    StoreToLayout,
 }
 
 // Validation you perform during the genesis process. Nobody else on the network performs it, only you.
@@ -42,21 +53,27 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
         FlatOp::StoreEntry(store_entry) => match store_entry {
             OpEntry::CreateEntry { app_entry, action } => match app_entry {
                 EntryTypes::Product(product) => {
-                    validate_create_product(EntryCreationAction::Create(action), product)
+                    validate_create_product(EntryCreationAction::Create(action), *product)
                 }
                 EntryTypes::ProductGroup(product_group) => {
                     validate_create_product_group(EntryCreationAction::Create(action), product_group)
                 }
+                EntryTypes::StoreLayout(store_layout) => {
+                    validate_create_store_layout(EntryCreationAction::Create(action), store_layout)
+                }
             },
             OpEntry::UpdateEntry {
                 app_entry, action, ..
             } => match app_entry {
                 EntryTypes::Product(product) => {
-                    validate_create_product(EntryCreationAction::Update(action), product)
+                    validate_create_product(EntryCreationAction::Update(action), *product)
                 }
                 EntryTypes::ProductGroup(product_group) => {
                     validate_create_product_group(EntryCreationAction::Update(action), product_group)
                 }
+                EntryTypes::StoreLayout(store_layout) => {
+                    validate_create_store_layout(EntryCreationAction::Update(action), store_layout)
+                }
             },
             _ => Ok(ValidateCallbackResult::Valid),
         },
@@ -87,7 +104,7 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                         };
                         validate_update_product(
                             action,
-                            product,
+                            *product,
                             original_create_action,
                             original_product,
                         )
@@ -110,6 +127,24 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                             original_product_group,
                         )
                     }
+                    EntryTypes::StoreLayout(store_layout) => {
+                        let original_app_entry =
+                            must_get_valid_record(action.clone().original_action_address)?;
+                        let original_store_layout = match StoreLayout::try_from(original_app_entry) {
+                            Ok(entry) => entry,
+                            Err(e) => {
+                                return Ok(ValidateCallbackResult::Invalid(format!(
+                                    "Expected to get StoreLayout from Record: {e:?}"
+                                )));
+                            }
+                        };
+                        validate_update_store_layout(
+                            action,
+                            store_layout,
+                            original_create_action,
+                            original_store_layout,
+                        )
+                    }
                 }
             }
             _ => Ok(ValidateCallbackResult::Valid),
@@ -157,13 +192,18 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                 EntryTypes::Product(original_product) => validate_delete_product(
                     delete_entry.clone().action,
                     original_action,
-                    original_product,
+                    *original_product,
                 ),
                 EntryTypes::ProductGroup(original_product_group) => validate_delete_product_group(
                     delete_entry.clone().action,
                     original_action,
                     original_product_group,
                 ),
+                EntryTypes::StoreLayout(original_store_layout) => validate_delete_store_layout(
+                    delete_entry.clone().action,
+                    original_action,
+                    original_store_layout,
+                ),
             }
         }
         FlatOp::RegisterCreateLink {
@@ -171,9 +211,10 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
             base_address: _,
             target_address: _,
             tag: _,
-            action: _,
+            action,
         } => match link_type {
             LinkTypes::ProductTypeToGroup => Ok(ValidateCallbackResult::Valid),
+            LinkTypes::StoreToLayout => validate_store_layout_link(&action.author),
         },
         FlatOp::RegisterDeleteLink {
             link_type,
@@ -181,20 +222,24 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
             target_address: _,
             tag: _,
             original_action: _,
-            action: _,
+            action,
         } => match link_type {
             LinkTypes::ProductTypeToGroup => Ok(ValidateCallbackResult::Valid),
+            LinkTypes::StoreToLayout => validate_store_layout_link(&action.author),
         },
         // The rest of the validation callbacks remain the same as in the original file
         FlatOp::StoreRecord(store_record) => match store_record {
             // Include validation for both Product and ProductGroup
             OpRecord::CreateEntry { app_entry, action } => match app_entry {
                 EntryTypes::Product(product) => {
-                    validate_create_product(EntryCreationAction::Create(action), product)
+                    validate_create_product(EntryCreationAction::Create(action), *product)
                 }
                 EntryTypes::ProductGroup(product_group) => {
                     validate_create_product_group(EntryCreationAction::Create(action), product_group)
                 }
+                EntryTypes::StoreLayout(store_layout) => {
+                    validate_create_store_layout(EntryCreationAction::Create(action), store_layout)
+                }
             },
             OpRecord::UpdateEntry {
                 original_action_hash,
@@ -218,7 +263,7 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     EntryTypes::Product(product) => {
                         let result = validate_create_product(
                             EntryCreationAction::Update(action.clone()),
-                            product.clone(),
+                            *product.clone(),
                         )?;
                         if let ValidateCallbackResult::Valid = result {
                             let original_product: Option<Product> = original_record
@@ -238,7 +283,7 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                             };
                             validate_update_product(
                                 action,
-                                product,
+                                *product,
                                 original_action,
                                 original_product,
                             )
@@ -277,6 +322,17 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                             Ok(result)
                         }
                     }
+                    EntryTypes::StoreLayout(store_layout) => {
+                        let original_store_layout = match StoreLayout::try_from(original_record) {
+                            Ok(entry) => entry,
+                            Err(e) => {
+                                return Ok(ValidateCallbackResult::Invalid(format!(
+                                    "Expected to get StoreLayout from Record: {e:?}"
+                                )));
+                            }
+                        };
+                        validate_update_store_layout(action, store_layout, original_action, original_store_layout)
+                    }
                 }
             }
             // Remainder of the validation callbacks are similar to the original file
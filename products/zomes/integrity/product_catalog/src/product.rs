@@ -1,11 +1,13 @@
 use hdi::prelude::*;
 
+use crate::{Money, MONEY_SCHEMA_VERSION};
+
 #[derive(Clone, PartialEq)] // We only need Clone and PartialEq here if hdk_entry_helper provides the others
 #[hdk_entry_helper]
 pub struct Product {
     pub name: String,
-    pub price: f32,
-    pub promo_price: Option<f32>,
+    pub price: Money,
+    pub promo_price: Option<Money>,
     pub size: String,
     pub stocks_status: String,
     pub category: String,
@@ -19,6 +21,32 @@ pub struct Product {
     pub embedding: Option<Vec<f32>>,
     pub brand: Option<String>,
     pub is_organic: Option<bool>,
+    // 0 for legacy entries with float prices - Money reads those transparently
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+impl Product {
+    // Bring a (possibly legacy) product up to the current schema - prices were already converted on read
+    pub fn migrated(mut self) -> Self {
+        self.schema_version = MONEY_SCHEMA_VERSION;
+        self
+    }
+}
+
+// EntryTypes boxes its Product variant - these forward the entry conversions to the inner Product
+impl TryFrom<&Box<Product>> for SerializedBytes {
+    type Error = SerializedBytesError;
+    fn try_from(product: &Box<Product>) -> Result<Self, Self::Error> {
+        SerializedBytes::try_from(product.as_ref())
+    }
+}
+
+impl TryFrom<&Entry> for Box<Product> {
+    type Error = WasmError;
+    fn try_from(entry: &Entry) -> Result<Self, Self::Error> {
+        Product::try_from(entry).map(Box::new)
+    }
 }
 
 // New ProductGroup struct that contains multiple products
@@ -67,11 +95,23 @@ pub fn validate_create_product(
             "Product name cannot be empty".into(),
         ));
     }
-    if product.price < 0.0 {
+    if product.schema_version != MONEY_SCHEMA_VERSION {
+        return Ok(ValidateCallbackResult::Invalid(
+            format!("Products must be written with schema version {}", MONEY_SCHEMA_VERSION),
+        ));
+    }
+    if product.price.is_negative() {
         return Ok(ValidateCallbackResult::Invalid(
             "Price cannot be negative".into(),
         ));
     }
+    if let Some(promo_price) = &product.promo_price {
+        if promo_price.is_negative() || promo_price.currency != product.price.currency {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Promo price must be non-negative and in the same currency as the price".into(),
+            ));
+        }
+    }
 
     // Validate sold_by if present
     if let Some(sold_by) = &product.sold_by {
@@ -0,0 +1,106 @@
+use hdi::prelude::*;
+
+use crate::is_admin_agent;
+
+// Where a product is kept cold - pick lists walk Ambient, then Chilled, then Frozen
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TemperatureZone {
+    Ambient,
+    Chilled,
+    Frozen,
+}
+
+// Counter where WEIGHT items are weighed and labelled
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ScaleStation {
+    Deli,
+    Produce,
+}
+
+// Aisle and section for a category, subcategory or product type path - the most specific match wins
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AisleLocation {
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub product_type: Option<String>,
+    pub aisle: String,
+    pub section: String,
+    pub walk_order: u32, // Position on the shopper's walk through the store, lowest first
+    pub temperature_zone: TemperatureZone,
+    pub scale_station: Option<ScaleStation>,
+}
+
+// One store's floor plan, maintained by the admin - linked from "store_layouts/{store_id}"
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct StoreLayout {
+    pub store_id: String,
+    pub locations: Vec<AisleLocation>,
+    pub updated_at: u64,
+}
+
+pub fn validate_create_store_layout(
+    action: EntryCreationAction,
+    store_layout: StoreLayout,
+) -> ExternResult<ValidateCallbackResult> {
+    if !is_admin_agent(action.author())? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the admin can maintain store layouts".into(),
+        ));
+    }
+    if store_layout.store_id.trim().is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Store layouts need a store id".into(),
+        ));
+    }
+    for location in &store_layout.locations {
+        if location.category.trim().is_empty() || location.aisle.trim().is_empty() {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Layout locations need a category and an aisle".into(),
+            ));
+        }
+        if location.product_type.is_some() && location.subcategory.is_none() {
+            return Ok(ValidateCallbackResult::Invalid(
+                "A product type location must also name its subcategory".into(),
+            ));
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_update_store_layout(
+    action: Update,
+    store_layout: StoreLayout,
+    _original_action: EntryCreationAction,
+    original_store_layout: StoreLayout,
+) -> ExternResult<ValidateCallbackResult> {
+    if store_layout.store_id != original_store_layout.store_id {
+        return Ok(ValidateCallbackResult::Invalid(
+            "A store layout cannot move to another store".into(),
+        ));
+    }
+    validate_create_store_layout(EntryCreationAction::Update(action), store_layout)
+}
+
+pub fn validate_delete_store_layout(
+    action: Delete,
+    _original_action: EntryCreationAction,
+    _original_store_layout: StoreLayout,
+) -> ExternResult<ValidateCallbackResult> {
+    if !is_admin_agent(&action.author)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the admin can delete store layouts".into(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// Only the admin links layouts to stores (or unlinks replaced ones)
+pub fn validate_store_layout_link(author: &AgentPubKey) -> ExternResult<ValidateCallbackResult> {
+    if !is_admin_agent(author)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "Only the admin can link store layouts".into(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,7 @@
+// Shared by cart_integrity and products_integrity - cart products store a ProductRef as their product_id
+// and carry the admin's signed PriceQuote, so both DNAs must agree on the serialized form
+mod price_quote;
+pub use price_quote::*;
+
+mod product_ref;
+pub use product_ref::*;
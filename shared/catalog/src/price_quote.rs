@@ -0,0 +1,19 @@
+use hdi::prelude::*;
+
+use money::Money;
+
+// Admin's statement of a product's price until expires_at (microseconds) - the cart verifies the
+// signature over this exact serialized form
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceQuote {
+    pub product_id: String,
+    pub price: Money,
+    pub promo_price: Option<Money>,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedPriceQuote {
+    pub quote: PriceQuote,
+    pub signature: Signature,
+}
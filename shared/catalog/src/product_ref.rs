@@ -0,0 +1,67 @@
+use hdi::prelude::*;
+
+// Where a product lives in the catalog - cart products store the string form as their product_id.
+// The string form is the legacy `{group_hash}:{index}` followed by optional `;key=value` parts:
+//   uhCkk...:3;catalog=2024-06;id=0001111041700;upc=011110417005
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProductRef {
+    // Network seed or version of the catalog the group was read from
+    pub catalog: Option<String>,
+    pub group_hash: ActionHash,
+    pub index: u32,
+    // Stable ids that survive re-seeding the catalog - used to find the product when the index moved
+    pub product_id: Option<String>,
+    pub upc: Option<String>,
+}
+
+impl ProductRef {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = value.split(';');
+        let location = parts.next().unwrap_or_default();
+        let (group_hash, index) = location
+            .rsplit_once(':')
+            .ok_or(format!("Product ref {} must start with group_hash:index", value))?;
+        let mut product_ref = ProductRef {
+            catalog: None,
+            group_hash: ActionHash::try_from(group_hash.to_string())
+                .map_err(|_| format!("Product ref {} has an invalid group hash", value))?,
+            index: index
+                .parse()
+                .map_err(|_| format!("Product ref {} has an invalid index", value))?,
+            product_id: None,
+            upc: None,
+        };
+
+        for part in parts {
+            let (key, field) = part
+                .split_once('=')
+                .ok_or(format!("Product ref part {} must be key=value", part))?;
+            if field.is_empty() {
+                return Err(format!("Product ref part {} has no value", key));
+            }
+            let slot = match key {
+                "catalog" => &mut product_ref.catalog,
+                "id" => &mut product_ref.product_id,
+                "upc" => &mut product_ref.upc,
+                _ => return Err(format!("Unknown product ref part {}", key)),
+            };
+            if slot.replace(field.to_string()).is_some() {
+                return Err(format!("Product ref part {} is repeated", key));
+            }
+        }
+        Ok(product_ref)
+    }
+}
+
+// Optional parts are always written in the same order so equal refs serialize the same
+impl std::fmt::Display for ProductRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.group_hash, self.index)?;
+        for (key, field) in [("catalog", &self.catalog), ("id", &self.product_id), ("upc", &self.upc)] {
+            if let Some(field) = field {
+                write!(f, ";{}={}", key, field)?;
+            }
+        }
+        Ok(())
+    }
+}
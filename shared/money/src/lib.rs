@@ -0,0 +1,119 @@
+use hdi::prelude::*;
+
+// Shared by cart_integrity and products_integrity - CartProduct snapshots copy catalog prices as-is,
+// so both DNAs must agree on the serialized form
+pub const DEFAULT_CURRENCY: &str = "USD";
+pub const MINOR_UNITS_PER_MAJOR: i64 = 100;
+
+// Entries written before Money stored prices as float major units (schema version 0)
+pub const MONEY_SCHEMA_VERSION: u32 = 1;
+
+// An exact amount in integer minor units (cents) of a currency
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: String,
+}
+
+// Wire forms Money accepts - the current struct, or a bare float from a legacy entry
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneyRepr {
+    Current { amount_minor: i64, currency: String },
+    LegacyMajorUnits(f64),
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match MoneyRepr::deserialize(deserializer)? {
+            MoneyRepr::Current { amount_minor, currency } => Ok(Money { amount_minor, currency }),
+            MoneyRepr::LegacyMajorUnits(amount) => Ok(Money::from_major_units(amount, DEFAULT_CURRENCY)),
+        }
+    }
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: &str) -> Self {
+        Money { amount_minor, currency: currency.to_string() }
+    }
+
+    pub fn zero(currency: &str) -> Self {
+        Money::new(0, currency)
+    }
+
+    // Round a float major-unit amount (e.g. 1.99 from a legacy f32 price) to the nearest minor unit
+    pub fn from_major_units(amount: f64, currency: &str) -> Self {
+        Money::new((amount * MINOR_UNITS_PER_MAJOR as f64).round() as i64, currency)
+    }
+
+    // For display only - never do arithmetic on the result
+    pub fn to_major_units(&self) -> f64 {
+        self.amount_minor as f64 / MINOR_UNITS_PER_MAJOR as f64
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount_minor < 0
+    }
+
+    // Price a quantity (units or weight) - rounded to the nearest minor unit
+    pub fn times(&self, quantity: f64) -> Self {
+        Money::new((self.amount_minor as f64 * quantity).round() as i64, &self.currency)
+    }
+
+    // None when the currencies differ or the sum overflows
+    pub fn checked_add(&self, other: &Money) -> Option<Self> {
+        if self.currency != other.currency {
+            return None;
+        }
+        self.amount_minor.checked_add(other.amount_minor).map(|amount| Money::new(amount, &self.currency))
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Option<Self> {
+        if self.currency != other.currency {
+            return None;
+        }
+        self.amount_minor.checked_sub(other.amount_minor).map(|amount| Money::new(amount, &self.currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_major_units_rounds_float_noise_away() {
+        assert_eq!(Money::from_major_units(1.99, "USD"), Money::new(199, "USD"));
+        assert_eq!(Money::from_major_units(0.1 + 0.2, "USD"), Money::new(30, "USD"));
+        assert_eq!(Money::from_major_units(-4.255, "USD").amount_minor, -426);
+        assert_eq!(Money::new(1999, "USD").to_major_units(), 19.99);
+    }
+
+    #[test]
+    fn times_rounds_to_the_nearest_minor_unit() {
+        assert_eq!(Money::new(199, "USD").times(3.0), Money::new(597, "USD"));
+        assert_eq!(Money::new(199, "USD").times(1.5), Money::new(299, "USD"));
+        assert_eq!(Money::new(349, "USD").times(0.455), Money::new(159, "USD"));
+        assert_eq!(Money::new(349, "USD").times(0.0), Money::zero("USD"));
+    }
+
+    #[test]
+    fn checked_add_and_sub_keep_the_currency() {
+        let price = Money::new(250, "USD");
+        assert_eq!(price.checked_add(&Money::new(75, "USD")), Some(Money::new(325, "USD")));
+        assert_eq!(price.checked_sub(&Money::new(300, "USD")), Some(Money::new(-50, "USD")));
+        assert!(price.checked_sub(&Money::new(300, "USD")).unwrap().is_negative());
+        assert!(!Money::zero("USD").is_negative());
+    }
+
+    #[test]
+    fn checked_add_and_sub_refuse_mixed_currencies_and_overflow() {
+        let price = Money::new(250, "USD");
+        assert_eq!(price.checked_add(&Money::new(250, "EUR")), None);
+        assert_eq!(price.checked_sub(&Money::new(250, "EUR")), None);
+        assert_eq!(Money::new(i64::MAX, "USD").checked_add(&Money::new(1, "USD")), None);
+        assert_eq!(Money::new(i64::MIN, "USD").checked_sub(&Money::new(1, "USD")), None);
+    }
+}